@@ -0,0 +1,53 @@
+// The darkness covering the view at night, see `game::lighting`.
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Keep in sync with `MAX_LIGHTS` in `game::lighting`
+const MAX_LIGHTS: u32 = 32u;
+// Color of the unlit parts of the level
+const NIGHT_COLOR: vec3<f32> = vec3<f32>(0.01, 0.015, 0.04);
+// Share of the darkness left in fully lit areas, tinted by the lights
+const LIT_DARKNESS: f32 = 0.15;
+
+struct Light {
+  color: vec4<f32>,
+  position: vec2<f32>,
+  direction: vec2<f32>,
+  radius: f32,
+  spread: f32,
+}
+
+struct Lighting {
+  darkness: f32,
+  count: u32,
+  lights: array<Light, MAX_LIGHTS>,
+}
+
+@group(2) @binding(0) var<uniform> lighting: Lighting;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+  let position = mesh.world_position.xy;
+  var light = vec3<f32>(0.);
+
+  for (var i = 0u; i < min(lighting.count, MAX_LIGHTS); i++) {
+    let source = lighting.lights[i];
+    let offset = position - source.position;
+    let distance = length(offset);
+
+    var falloff = clamp(1. - distance / source.radius, 0., 1.);
+    falloff *= falloff;
+
+    // Cones only light in front of them, with soft edges
+    if source.spread > -1. && distance > 0. {
+      let alignment = dot(offset / distance, source.direction);
+      falloff *= smoothstep(source.spread, source.spread + 0.1, alignment);
+    }
+
+    light += source.color.rgb * source.color.a * falloff;
+  }
+
+  let brightness = clamp(max(light.r, max(light.g, light.b)), 0., 1.);
+  let color = mix(NIGHT_COLOR, clamp(light, vec3<f32>(0.), vec3<f32>(1.)), brightness);
+  let alpha = lighting.darkness * (1. - brightness * (1. - LIT_DARKNESS));
+  return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,25 @@
+// Splits the color channels towards the edges of the screen, see `game::post_process`.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct ChromaticAberration {
+  intensity: f32,
+  offset: f32,
+  _padding: vec2<f32>,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: ChromaticAberration;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+  // The channels drift apart away from the center
+  let offset = (in.uv - 0.5) * settings.offset * settings.intensity;
+
+  return vec4<f32>(
+    textureSample(screen_texture, texture_sampler, in.uv + offset).r,
+    textureSample(screen_texture, texture_sampler, in.uv).g,
+    textureSample(screen_texture, texture_sampler, in.uv - offset).b,
+    1.
+  );
+}
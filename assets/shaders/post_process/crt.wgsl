@@ -0,0 +1,34 @@
+// Bends the screen and draws scanlines over it, like an old monitor, see `game::post_process`.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Crt {
+  intensity: f32,
+  scanlines: f32,
+  curvature: f32,
+  _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: Crt;
+
+const PI: f32 = 3.14159265;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+  // Push the picture away from the center, more in the corners
+  let centered = in.uv * 2. - 1.;
+  let bend = 1. + dot(centered, centered) * settings.curvature * settings.intensity;
+  let uv = centered * bend * 0.5 + 0.5;
+
+  let color = textureSample(screen_texture, texture_sampler, uv);
+  let scanline = 0.5 + 0.5 * cos(uv.y * settings.scanlines * 2. * PI);
+  var shade = 1. - settings.intensity * 0.35 * (1. - scanline);
+
+  // Black outside of the bent screen
+  if any(uv < vec2<f32>(0.)) || any(uv > vec2<f32>(1.)) {
+    shade = 0.;
+  }
+
+  return vec4<f32>(color.rgb * shade, 1.);
+}
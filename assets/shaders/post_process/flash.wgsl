@@ -0,0 +1,16 @@
+// Washes the screen with a color, see `game::post_process`.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Flash {
+  color: vec4<f32>,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: Flash;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(screen_texture, texture_sampler, in.uv);
+  return vec4<f32>(mix(color.rgb, settings.color.rgb, settings.color.a), 1.);
+}
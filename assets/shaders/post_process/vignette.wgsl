@@ -0,0 +1,24 @@
+// Darkens the corners of the screen, see `game::post_process`.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct Vignette {
+  intensity: f32,
+  radius: f32,
+  softness: f32,
+  _padding: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: Vignette;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(screen_texture, texture_sampler, in.uv);
+
+  // 0 at the center, about 1 in the corners
+  let distance = length(in.uv - 0.5) * 1.414;
+  let shade = smoothstep(settings.radius, settings.radius + settings.softness, distance);
+
+  return vec4<f32>(color.rgb * (1. - shade * settings.intensity), 1.);
+}
@@ -41,7 +41,7 @@ pub enum AppState {
 
 #[allow(dead_code)]
 /// [`SubStates`] for the [`AppState::MainMenu`] state.
-#[derive(SubStates, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(SubStates, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 #[source(AppState = AppState::MainMenu)]
 pub enum MainMenuSubState {
   #[default]
@@ -58,6 +58,18 @@ pub enum MainMenuSubState {
   /// The user can see the credits of the application.
   /// The user can return to the main menu [`MainMenuSubState::None`].
   Credits,
+  /// The `achievements` [`SubStates`] of the application.
+  /// In this state the application is displaying the achievements and their progress.
+  /// The user can return to the main menu [`MainMenuSubState::None`].
+  Achievements,
+  /// The `upgrades` [`SubStates`] of the application.
+  /// In this state the application is displaying the upgrades bought between runs.
+  /// The user can return to the main menu [`MainMenuSubState::None`].
+  Upgrades,
+  /// The `level_select` [`SubStates`] of the application.
+  /// In this state the application is displaying the arenas of the endless mode.
+  /// The user can return to the main menu [`MainMenuSubState::None`].
+  LevelSelect,
 }
 
 #[allow(dead_code)]
@@ -75,6 +87,10 @@ pub enum InGameSubState {
   /// The user can resume the game [`InGameSubState::None`],
   /// or return to the main menu [`AppState::MainMenu`].
   Paused,
+  /// The `draft` [`SubStates`] of the game.
+  /// In this state the gameplay is paused between two cycles,
+  /// while the user picks an upgrade for the rest of the run.
+  Draft,
 }
 
 /// Marker component that is used to despawn all entities that are in a specific state.
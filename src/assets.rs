@@ -1,6 +1,13 @@
-use crate::prelude::*;
+use std::marker::PhantomData;
+
+use crate::{
+  game::{draft::DraftPool, level::Levels},
+  prelude::*,
+};
+use bevy::asset::{io::Reader, AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext};
 use bevy_ecs_ldtk::assets::LdtkProject;
 use iyes_progress::prelude::*;
+use serde::de::DeserializeOwned;
 
 pub struct AssetsLoadingPlugin;
 
@@ -10,9 +17,10 @@ pub struct UiAssets {
   pub font_sans: Handle<Font>,
   #[allow(unused)]
   pub font_mono: Handle<Font>,
-  #[allow(unused)]
   pub atlas: Handle<Image>,
-  pub planet: Handle<LdtkProject>,
+  /// Kept loaded so the arenas switch without waiting, see [`crate::game::level`].
+  #[allow(unused)]
+  pub arenas: Vec<Handle<LdtkProject>>,
   pub player_spritesheet: Handle<Image>,
   pub enemy_red_spritesheet: Handle<Image>,
   pub enemy_blue_spritesheet: Handle<Image>,
@@ -37,12 +45,77 @@ pub struct MainMenuAssets {
   pub wasd_icon: Handle<Image>,
 }
 
+#[derive(Resource)]
+/// Gameplay data, described by the RON files in `assets/data`
+pub struct DataAssets {
+  pub draft_pool: Handle<DraftPool>,
+  pub levels: Handle<Levels>,
+}
+
 impl Plugin for AssetsLoadingPlugin {
   fn build(&self, app: &mut App) {
+    app.init_asset::<DraftPool>();
+    app.register_asset_loader(RonLoader::<DraftPool>::default());
+    app.init_asset::<Levels>();
+    app.register_asset_loader(RonLoader::<Levels>::default());
+
     app.add_systems(
       OnEnter(AppState::AssetsLoading),
-      (load_ui_assets, load_main_menu_audio_assets),
+      (
+        load_ui_assets,
+        load_main_menu_audio_assets,
+        load_data_assets,
+      ),
+    );
+    app.add_systems(
+      Update,
+      (
+        fail_on_data_error::<DraftPool>,
+        fail_on_data_error::<Levels>,
+      )
+        .run_if(in_state(AppState::AssetsLoading)),
     );
+    app.add_systems(OnExit(AppState::AssetsLoading), insert_data_resources);
+  }
+}
+
+/// Gameplay data described by a RON file, checked once it is parsed.
+pub trait DataAsset: Asset + DeserializeOwned {
+  /// A failed check fails the load, with the returned message.
+  fn validate(&self) -> Result<(), String> {
+    Ok(())
+  }
+}
+
+/// Loads an asset of type `A` from a RON file.
+pub struct RonLoader<A>(PhantomData<A>);
+
+impl<A> Default for RonLoader<A> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<A: DataAsset> AssetLoader for RonLoader<A> {
+  type Asset = A;
+  type Settings = ();
+  type Error = Box<dyn std::error::Error + Send + Sync>;
+
+  async fn load<'a>(
+    &'a self,
+    reader: &'a mut Reader<'_>,
+    _settings: &'a (),
+    _load_context: &'a mut LoadContext<'_>,
+  ) -> Result<A, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    let asset: A = ron::de::from_bytes(&bytes)?;
+    asset.validate()?;
+    Ok(asset)
+  }
+
+  fn extensions(&self) -> &[&str] {
+    &["ron"]
   }
 }
 
@@ -60,8 +133,13 @@ fn load_ui_assets(
   let font_mono = asset_server.load("fonts/JetBrainsMono.ttf");
   let atlas = asset_server.load("textures/1-bit/colored.png");
 
+  // LDtk projects of the arenas of the level select screen
+  let arenas: Vec<Handle<LdtkProject>> = vec![
+    asset_server.load("levels/walls.ldtk"),
+    asset_server.load("levels/planet.ldtk"),
+  ];
+
   // player spritesheet
-  let planet = asset_server.load("levels/walls.ldtk");
   let player_spritesheet: Handle<Image> = asset_server.load("textures/player/player_norm.png");
 
   // enemy spritesheets
@@ -93,7 +171,9 @@ fn load_ui_assets(
   loading.add(&font_sans);
   loading.add(&font_mono);
   loading.add(&atlas);
-  loading.add(&planet);
+  for arena in arenas.iter() {
+    loading.add(arena);
+  }
   loading.add(&player_spritesheet);
   loading.add(&dead_enemy_sprite);
   loading.add(&dead_enemy_red_sprite);
@@ -118,7 +198,7 @@ fn load_ui_assets(
     font_sans,
     font_mono,
     atlas,
-    planet,
+    arenas,
     player_spritesheet,
     enemy_blue_spritesheet,
     enemy_green_spritesheet,
@@ -166,3 +246,45 @@ pub fn load_main_menu_audio_assets(
     wasd_icon,
   });
 }
+
+/// Loading the gameplay data and tracking the progress
+/// using the [`AssetsLoading`] resource by `iyes_progress`.
+fn load_data_assets(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  mut loading: ResMut<AssetsLoading>,
+) {
+  let draft_pool = asset_server.load("data/draft.ron");
+  let levels = asset_server.load("data/levels.ron");
+
+  loading.add(&draft_pool);
+  loading.add(&levels);
+
+  commands.insert_resource(DataAssets { draft_pool, levels });
+}
+
+/// The game can not run without its data, the loading screen would wait for it forever.
+fn fail_on_data_error<A: DataAsset>(mut failed_events: EventReader<AssetLoadFailedEvent<A>>) {
+  if let Some(event) = failed_events.read().next() {
+    error!("Could not load {}: {}", event.path, event.error);
+    panic!("the gameplay data of {} is missing", event.path);
+  }
+}
+
+/// The gameplay reads the data as resources, they are copied once loaded.
+fn insert_data_resources(
+  mut commands: Commands,
+  data: Res<DataAssets>,
+  draft_pools: Res<Assets<DraftPool>>,
+  levels: Res<Assets<Levels>>,
+) {
+  let draft_pool = draft_pools
+    .get(&data.draft_pool)
+    .expect("data/draft.ron should be loaded");
+  commands.insert_resource(draft_pool.clone());
+
+  let levels = levels
+    .get(&data.levels)
+    .expect("data/levels.ron should be loaded");
+  commands.insert_resource(levels.clone());
+}
@@ -51,6 +51,10 @@ pub const CYCLE_WEIGHT: usize = 3;
 /// The delay between meteor spawns, expressed in seconds.
 pub const METEOR_SPAWN_DELAY: f32 = 0.25;
 
+/// The size of the visible world, scaled by whole numbers to fit the window.
+pub const VIRTUAL_WIDTH: f32 = 640.0;
+pub const VIRTUAL_HEIGHT: f32 = 360.0;
+
 // z-indexes
 pub const PLAYER_Z_INDEX: f32 = 4.0;
 pub const CAMERA_Z_INDEX: f32 = 10.0;
@@ -62,6 +66,9 @@ pub const ATTACK_GROUP: Group = Group::GROUP_1;
 pub const ENEMY_GROUP: Group = Group::GROUP_2;
 pub const PLAYER_GROUP: Group = Group::GROUP_3;
 pub const ATTACK_TRAIL_GROUP: Group = Group::GROUP_4;
+pub const POWER_UP_GROUP: Group = Group::GROUP_5;
+pub const TERRAIN_GROUP: Group = Group::GROUP_6;
+pub const COVER_GROUP: Group = Group::GROUP_7;
 
 // attack
 pub const MIN_ATTACK_AREA: f32 = 10_000.0;
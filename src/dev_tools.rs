@@ -1,12 +1,16 @@
-use bevy::{dev_tools::states::log_transitions, input::common_conditions::input_toggle_active};
+use bevy::{dev_tools::states::log_transitions, input::InputSystem};
 use bevy_inspector_egui::quick::{StateInspectorPlugin, WorldInspectorPlugin};
 use bevy_rapier2d::render::RapierDebugRenderPlugin;
 
-use crate::prelude::*;
+use crate::{prelude::*, screens::main_menu::SeedInput};
 
 /// A Bevy plugin that runs only when the `dev` feature is enabled.
 pub struct DevToolsPlugin;
 
+/// Whether the inspectors are shown.
+#[derive(Resource)]
+struct InspectorActive(bool);
+
 impl Plugin for DevToolsPlugin {
   fn build(&self, app: &mut App) {
     // Track all [`AppState`] transitions
@@ -17,12 +21,28 @@ impl Plugin for DevToolsPlugin {
     // Beware that when these plugins are active, cursor icon won't change
     // Add the World and State inspector plugins by `bevy-inspector-egui`
     // The interface can be toggled by pressing the `Escape` key
+    app.insert_resource(InspectorActive(true));
+    app.add_systems(PreUpdate, toggle_inspector.after(InputSystem));
     app.add_plugins((
-      WorldInspectorPlugin::default().run_if(input_toggle_active(true, KeyCode::Escape)),
-      StateInspectorPlugin::<AppState>::default()
-        .run_if(input_toggle_active(true, KeyCode::Escape)),
-      StateInspectorPlugin::<CycleState>::default()
-        .run_if(input_toggle_active(true, KeyCode::Escape)),
+      WorldInspectorPlugin::default().run_if(inspector_active),
+      StateInspectorPlugin::<AppState>::default().run_if(inspector_active),
+      StateInspectorPlugin::<CycleState>::default().run_if(inspector_active),
     ));
   }
 }
+
+/// `Escape` also leaves the seed input, the inspectors stay as they are then.
+/// It runs before the input is handled in `Update`, while the seed input is still focused.
+fn toggle_inspector(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  seed_query: Query<&SeedInput>,
+  mut active: ResMut<InspectorActive>,
+) {
+  if keyboard_input.just_pressed(KeyCode::Escape) && !seed_query.iter().any(SeedInput::is_focused) {
+    active.0 = !active.0;
+  }
+}
+
+fn inspector_active(active: Res<InspectorActive>) -> bool {
+  active.0
+}
@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{assets::UiAssets, prelude::*, storage};
+
+use super::{
+  attack::AttackResolvedEvent, cycle::CycleCompletedEvent, enemy::EnemyVariant, input::InputSource,
+};
+
+const ACHIEVEMENTS_KEY: &str = "achievements";
+/// How long a toast stays on screen, expressed in seconds.
+const TOAST_DURATION: f32 = 3.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Achievement {
+  MultiKill,
+  Survivor,
+  StandYourGround,
+  FullSpectrum,
+}
+
+impl Achievement {
+  pub const ALL: [Achievement; 4] = [
+    Achievement::MultiKill,
+    Achievement::Survivor,
+    Achievement::StandYourGround,
+    Achievement::FullSpectrum,
+  ];
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      Achievement::MultiKill => "Massacre",
+      Achievement::Survivor => "Survivor",
+      Achievement::StandYourGround => "Stand Your Ground",
+      Achievement::FullSpectrum => "Full Spectrum",
+    }
+  }
+
+  pub fn description(&self) -> &'static str {
+    match self {
+      Achievement::MultiKill => "Kill 5 enemies with a single shape",
+      Achievement::Survivor => "Complete cycle 10",
+      Achievement::StandYourGround => "Complete a cycle without dodging",
+      Achievement::FullSpectrum => "Kill every kind of enemy with a single shape",
+    }
+  }
+
+  /// The value of the progress needed to unlock the achievement.
+  pub fn target(&self) -> usize {
+    match self {
+      Achievement::MultiKill => 5,
+      Achievement::Survivor => 10,
+      Achievement::StandYourGround => 1,
+      Achievement::FullSpectrum => EnemyVariant::ALL.len(),
+    }
+  }
+}
+
+/// The achievements unlocked on this device, with the best progress towards the others.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Achievements {
+  pub unlocked: Vec<Achievement>,
+  pub best_multi_kill: usize,
+  pub best_cycle: usize,
+  pub best_variants: usize,
+}
+
+impl Achievements {
+  pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+    self.unlocked.contains(&achievement)
+  }
+
+  pub fn progress(&self, achievement: Achievement) -> usize {
+    let progress = match achievement {
+      Achievement::MultiKill => self.best_multi_kill,
+      Achievement::Survivor => self.best_cycle,
+      Achievement::StandYourGround => self.is_unlocked(achievement) as usize,
+      Achievement::FullSpectrum => self.best_variants,
+    };
+
+    progress.min(achievement.target())
+  }
+
+  /// Unlock the achievement, returning `false` if it was already unlocked.
+  fn unlock(&mut self, achievement: Achievement) -> bool {
+    if self.is_unlocked(achievement) {
+      return false;
+    }
+
+    self.unlocked.push(achievement);
+    true
+  }
+
+  fn save(&self) {
+    storage::save(ACHIEVEMENTS_KEY, self);
+  }
+}
+
+#[derive(Event)]
+pub struct AchievementUnlockedEvent(pub Achievement);
+
+/// Dodges since the start of the current cycle.
+#[derive(Resource, Default)]
+struct CycleDodges(usize);
+
+#[derive(Component)]
+struct ToastContainer;
+
+#[derive(Component)]
+struct AchievementToast {
+  timer: Timer,
+}
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(storage::load::<Achievements>(ACHIEVEMENTS_KEY).unwrap_or_default());
+    app.init_resource::<CycleDodges>();
+    app.add_event::<AchievementUnlockedEvent>();
+
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      (reset_cycle_dodges, spawn_toast_container),
+    );
+    // Replays are not runs, they do not unlock anything
+    app.add_systems(
+      PostUpdate,
+      (track_attacks, track_cycles, save_achievements)
+        .chain()
+        .run_if(in_state(AppState::InGame))
+        .run_if(resource_equals(InputSource::Live)),
+    );
+    app.add_systems(
+      Update,
+      (spawn_toasts, despawn_toasts).run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn reset_cycle_dodges(mut dodges: ResMut<CycleDodges>) {
+  dodges.0 = 0;
+}
+
+/// Raise the best value, returning `true` if it increased.
+///
+/// The achievements are only marked as changed when the progress increased,
+/// so that they are not saved again on every frame of combat.
+fn raise(best: &mut usize, value: usize) -> bool {
+  let raised = value > *best;
+  if raised {
+    *best = value;
+  }
+  raised
+}
+
+fn track_attacks(
+  mut achievements: ResMut<Achievements>,
+  mut resolved_events: EventReader<AttackResolvedEvent>,
+  mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+  let mut changed = false;
+  let progress = achievements.bypass_change_detection();
+
+  for event in resolved_events.read() {
+    changed |= raise(&mut progress.best_multi_kill, event.hits);
+    changed |= raise(&mut progress.best_variants, event.variants.len());
+
+    if event.hits >= Achievement::MultiKill.target() && progress.unlock(Achievement::MultiKill) {
+      changed = true;
+      unlocked_events.send(AchievementUnlockedEvent(Achievement::MultiKill));
+    }
+
+    if event.variants.len() >= Achievement::FullSpectrum.target()
+      && progress.unlock(Achievement::FullSpectrum)
+    {
+      changed = true;
+      unlocked_events.send(AchievementUnlockedEvent(Achievement::FullSpectrum));
+    }
+  }
+
+  if changed {
+    achievements.set_changed();
+  }
+}
+
+fn track_cycles(
+  mut achievements: ResMut<Achievements>,
+  mut dodges: ResMut<CycleDodges>,
+  mut dodged_events: EventReader<PlayerDodgedEvent>,
+  mut completed_events: EventReader<CycleCompletedEvent>,
+  mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+  dodges.0 += dodged_events.read().count();
+
+  let mut changed = false;
+  let progress = achievements.bypass_change_detection();
+
+  for event in completed_events.read() {
+    changed |= raise(&mut progress.best_cycle, event.index);
+
+    if event.index >= Achievement::Survivor.target() && progress.unlock(Achievement::Survivor) {
+      changed = true;
+      unlocked_events.send(AchievementUnlockedEvent(Achievement::Survivor));
+    }
+
+    if dodges.0 == 0 && progress.unlock(Achievement::StandYourGround) {
+      changed = true;
+      unlocked_events.send(AchievementUnlockedEvent(Achievement::StandYourGround));
+    }
+
+    dodges.0 = 0;
+  }
+
+  if changed {
+    achievements.set_changed();
+  }
+}
+
+/// Progress is saved as soon as it changes, so it survives the game being closed mid-run.
+fn save_achievements(achievements: Res<Achievements>) {
+  if achievements.is_changed() && !achievements.is_added() {
+    achievements.save();
+  }
+}
+
+fn spawn_toast_container(mut commands: Commands) {
+  commands.spawn((
+    StateDespawnMarker,
+    ToastContainer,
+    NodeBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(16.),
+        width: Val::Percent(100.),
+        flex_direction: FlexDirection::ColumnReverse,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(8.),
+        ..Default::default()
+      },
+      ..Default::default()
+    },
+  ));
+}
+
+fn spawn_toasts(
+  mut commands: Commands,
+  mut unlocked_events: EventReader<AchievementUnlockedEvent>,
+  container_query: Query<Entity, With<ToastContainer>>,
+  ui: Res<UiAssets>,
+) {
+  let Ok(container) = container_query.get_single() else {
+    return;
+  };
+
+  for AchievementUnlockedEvent(achievement) in unlocked_events.read() {
+    let toast = commands
+      .spawn((
+        AchievementToast {
+          timer: Timer::from_seconds(TOAST_DURATION, TimerMode::Once),
+        },
+        NodeBundle {
+          style: Style {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            padding: UiRect::axes(Val::Px(16.), Val::Px(8.)),
+            ..Default::default()
+          },
+          background_color: colors::PRIMARY_700.into(),
+          ..Default::default()
+        },
+      ))
+      .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+          format!("Achievement unlocked: {}", achievement.title()),
+          TextStyle {
+            font: ui.font_sans.clone(),
+            color: colors::RED_400,
+            font_size: 20.,
+          },
+        ));
+        parent.spawn(TextBundle::from_section(
+          achievement.description(),
+          TextStyle {
+            font: ui.font_sans.clone(),
+            color: colors::PRIMARY_300,
+            font_size: 16.,
+          },
+        ));
+      })
+      .id();
+
+    commands.entity(container).push_children(&[toast]);
+  }
+}
+
+fn despawn_toasts(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut AchievementToast)>,
+  time: Res<Time<Real>>,
+) {
+  for (entity, mut toast) in &mut query {
+    toast.timer.tick(time.delta());
+    if toast.timer.finished() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
@@ -0,0 +1,381 @@
+use std::collections::VecDeque;
+
+use bevy_ecs_ldtk::GridCoords;
+use bevy_rapier2d::prelude::Velocity;
+use rand::Rng;
+
+use crate::{assets::UiAssets, prelude::*};
+
+use super::{
+  camera::CameraBounds,
+  level::{CurrentLevel, LevelSource, Levels},
+  rng::{GameRng, RngStream},
+  tiles::{EnemySpawner, MeteorZone, PlayerStart, Wall},
+};
+
+/// The size of a generated arena in cells, the same as the LDtk levels.
+const ARENA_WIDTH: i32 = 58;
+const ARENA_HEIGHT: i32 = 52;
+/// Chance for a cell to start as a wall, before smoothing.
+const WALL_FILL: f64 = 0.42;
+const SMOOTHING_STEPS: usize = 5;
+/// Radius of the open area around the player start, expressed in cells.
+const START_CLEARING: i32 = 5;
+/// Radius of the enemy spawners, expressed in cells.
+const SPAWNER_RADIUS: f32 = 2.;
+/// Chance for a floor cell to be decorated.
+const DECORATION_CHANCE: f64 = 0.08;
+
+// Indices in `textures/1-bit/colored.png`
+const ATLAS_COLUMNS: u32 = 49;
+const ATLAS_ROWS: u32 = 22;
+/// The center of the 3x3 wall pattern, its neighbours are the edges and corners.
+const WALL_TILE: i32 = 68;
+const DECORATION_TILES: [usize; 7] = [1, 2, 3, 56, 57, 58, 61];
+
+/// The root of a generated arena, its children are the tiles and the spawn points.
+#[derive(Component)]
+struct Arena;
+
+/// The walls of a generated arena, with the spawn points fitting them.
+struct ArenaLayout {
+  walls: Vec<bool>,
+  start: IVec2,
+  spawners: Vec<IVec2>,
+}
+
+impl ArenaLayout {
+  fn index(cell: IVec2) -> usize {
+    (cell.y * ARENA_WIDTH + cell.x) as usize
+  }
+
+  fn contains(cell: IVec2) -> bool {
+    cell.x >= 0 && cell.y >= 0 && cell.x < ARENA_WIDTH && cell.y < ARENA_HEIGHT
+  }
+
+  /// Cells out of the arena are walls.
+  fn is_wall(&self, cell: IVec2) -> bool {
+    !Self::contains(cell) || self.walls[Self::index(cell)]
+  }
+
+  fn wall_neighbours(&self, cell: IVec2) -> usize {
+    (-1..=1)
+      .flat_map(|y| (-1..=1).map(move |x| IVec2::new(x, y)))
+      .filter(|offset| *offset != IVec2::ZERO && self.is_wall(cell + *offset))
+      .count()
+  }
+
+  /// Cellular automaton: random noise smoothed into caves, with an open center.
+  fn generate(rng: &mut impl Rng) -> Self {
+    let mut layout = Self {
+      walls: vec![false; (ARENA_WIDTH * ARENA_HEIGHT) as usize],
+      start: IVec2::new(ARENA_WIDTH / 2, ARENA_HEIGHT / 2),
+      spawners: Vec::new(),
+    };
+
+    for cell in cells() {
+      layout.walls[Self::index(cell)] = is_border(cell) || rng.gen_bool(WALL_FILL);
+    }
+
+    for _ in 0..SMOOTHING_STEPS {
+      let walls = cells()
+        .map(|cell| match layout.wall_neighbours(cell) {
+          _ if is_border(cell) => true,
+          5.. => true,
+          0..=3 => false,
+          _ => layout.is_wall(cell),
+        })
+        .collect();
+      layout.walls = walls;
+    }
+
+    for cell in cells() {
+      if (cell - layout.start).length_squared() <= START_CLEARING * START_CLEARING {
+        layout.walls[Self::index(cell)] = false;
+      }
+    }
+
+    // Caves the player can not reach are filled
+    let distances = layout.distances();
+    for cell in cells() {
+      if distances[Self::index(cell)].is_none() {
+        layout.walls[Self::index(cell)] = true;
+      }
+    }
+
+    layout.spawners = layout.place_spawners(&distances);
+    layout
+  }
+
+  /// Walking distance from the start to every cell, `None` for walls.
+  fn distances(&self) -> Vec<Option<u32>> {
+    let mut distances = vec![None; self.walls.len()];
+    let mut queue = VecDeque::from([(self.start, 0)]);
+    distances[Self::index(self.start)] = Some(0);
+
+    while let Some((cell, distance)) = queue.pop_front() {
+      for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+        let next = cell + offset;
+        if self.is_wall(next) || distances[Self::index(next)].is_some() {
+          continue;
+        }
+
+        distances[Self::index(next)] = Some(distance + 1);
+        queue.push_back((next, distance + 1));
+      }
+    }
+
+    distances
+  }
+
+  /// The open cell the furthest from the start in every quarter of the arena.
+  fn place_spawners(&self, distances: &[Option<u32>]) -> Vec<IVec2> {
+    let mut spawners: Vec<IVec2> = [(false, false), (true, false), (false, true), (true, true)]
+      .into_iter()
+      .filter_map(|(right, top)| {
+        cells()
+          .filter(|cell| (cell.x >= self.start.x) == right && (cell.y >= self.start.y) == top)
+          .filter(|cell| self.wall_neighbours(*cell) == 0)
+          .filter_map(|cell| distances[Self::index(cell)].map(|distance| (distance, cell)))
+          .max_by_key(|(distance, _)| *distance)
+          .map(|(_, cell)| cell)
+      })
+      .collect();
+
+    // The start is always open, enemies spawn there when the caves are too narrow
+    if spawners.is_empty() {
+      spawners.push(self.start);
+    }
+    spawners
+  }
+
+  /// The atlas index of a wall, picking the edges of the 3x3 pattern on the sides of wall blocks.
+  fn wall_tile(&self, cell: IVec2) -> usize {
+    let side =
+      |positive: IVec2| match (self.is_wall(cell - positive), self.is_wall(cell + positive)) {
+        (false, true) => -1,
+        (true, false) => 1,
+        _ => 0,
+      };
+
+    // The atlas rows go down while the cells go up
+    let column = side(IVec2::X);
+    let row = -side(IVec2::Y);
+    (WALL_TILE + column + row * ATLAS_COLUMNS as i32) as usize
+  }
+}
+
+fn cells() -> impl Iterator<Item = IVec2> {
+  (0..ARENA_HEIGHT).flat_map(|y| (0..ARENA_WIDTH).map(move |x| IVec2::new(x, y)))
+}
+
+fn is_border(cell: IVec2) -> bool {
+  cell.x == 0 || cell.y == 0 || cell.x == ARENA_WIDTH - 1 || cell.y == ARENA_HEIGHT - 1
+}
+
+/// Position of the center of a cell, relative to the arena.
+fn cell_center(cell: IVec2) -> Vec2 {
+  cell.as_vec2() * TILE_SPRITE_SIZE
+}
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      Update,
+      spawn_arena
+        .run_if(resource_changed::<CurrentLevel>)
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+/// Build a new arena every time a generated level is entered, and remove it when leaving.
+fn spawn_arena(
+  mut commands: Commands,
+  mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+  mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+  arena_query: Query<Entity, With<Arena>>,
+  mut rng: ResMut<GameRng>,
+  mut bounds: ResMut<CameraBounds>,
+  current_level: Res<CurrentLevel>,
+  levels: Res<Levels>,
+  ui: Res<UiAssets>,
+) {
+  for entity in &arena_query {
+    commands.entity(entity).despawn_recursive();
+  }
+
+  if levels.get(current_level.0).source != LevelSource::Generated {
+    return;
+  }
+
+  let size = Vec2::new(ARENA_WIDTH as f32, ARENA_HEIGHT as f32) * TILE_SPRITE_SIZE;
+  bounds.0 = Rect::from_corners(Vec2::ZERO, size);
+
+  let rng = rng.stream(RngStream::Arena);
+  let layout = ArenaLayout::generate(rng);
+  let atlas_layout = texture_atlas_layouts.add(TextureAtlasLayout::from_grid(
+    UVec2::splat(TILE_SPRITE_SIZE as u32),
+    ATLAS_COLUMNS,
+    ATLAS_ROWS,
+    Some(UVec2::ONE),
+    None,
+  ));
+
+  // Cells are centered on their coordinates, like the tiles of an LDtk layer
+  let origin = Vec2::splat(TILE_SPRITE_SIZE / 2.);
+  let tile = |cell: IVec2, z: f32| SpriteBundle {
+    texture: ui.atlas.clone(),
+    transform: Transform::from_translation(cell_center(cell).extend(z)),
+    ..Default::default()
+  };
+
+  commands
+    .spawn((
+      StateDespawnMarker,
+      Arena,
+      SpatialBundle::from_transform(Transform::from_translation(origin.extend(0.))),
+    ))
+    .with_children(|parent| {
+      for cell in cells() {
+        if layout.is_wall(cell) {
+          let index = layout.wall_tile(cell);
+          parent.spawn((
+            Wall,
+            GridCoords::from(cell),
+            tile(cell, 1.),
+            TextureAtlas {
+              layout: atlas_layout.clone(),
+              index,
+            },
+          ));
+        } else if rng.gen_bool(DECORATION_CHANCE) {
+          let index = DECORATION_TILES[rng.gen_range(0..DECORATION_TILES.len())];
+          parent.spawn((
+            tile(cell, 0.),
+            TextureAtlas {
+              layout: atlas_layout.clone(),
+              index,
+            },
+          ));
+        }
+      }
+
+      parent.spawn((
+        PlayerStart,
+        SpatialBundle::from_transform(Transform::from_translation(
+          cell_center(layout.start).extend(0.),
+        )),
+      ));
+
+      for spawner in layout.spawners.iter() {
+        parent.spawn((
+          EnemySpawner {
+            radius: SPAWNER_RADIUS * TILE_SPRITE_SIZE,
+          },
+          SpatialBundle::from_transform(Transform::from_translation(
+            cell_center(*spawner).extend(0.),
+          )),
+        ));
+      }
+
+      // Meteors fall anywhere within the borders
+      let inner_size = Vec2::new((ARENA_WIDTH - 2) as f32, (ARENA_HEIGHT - 2) as f32);
+      let center = Vec2::new((ARENA_WIDTH - 1) as f32, (ARENA_HEIGHT - 1) as f32) / 2.;
+      parent.spawn((
+        MeteorZone {
+          half_size: inner_size * TILE_SPRITE_SIZE / 2.,
+        },
+        SpatialBundle::from_transform(Transform::from_translation(
+          (center * TILE_SPRITE_SIZE).extend(0.),
+        )),
+      ));
+    });
+
+  if let Ok((mut transform, mut velocity)) = player_query.get_single_mut() {
+    let position = origin + cell_center(layout.start);
+    transform.translation = position.extend(transform.translation.z);
+    *velocity = Velocity::zero();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::game::rng::{GameRng, RngStream};
+
+  use super::*;
+
+  fn generate(seed: u64) -> ArenaLayout {
+    ArenaLayout::generate(GameRng::new(seed).stream(RngStream::Arena))
+  }
+
+  #[test]
+  fn same_seed_same_layout() {
+    let (first, second) = (generate(42), generate(42));
+    assert_eq!(first.walls, second.walls);
+    assert_eq!(first.spawners, second.spawners);
+  }
+
+  #[test]
+  fn borders_are_walls() {
+    for seed in 0..16 {
+      let layout = generate(seed);
+      for cell in cells().filter(|cell| is_border(*cell)) {
+        assert!(
+          layout.is_wall(cell),
+          "open border at {cell} with seed {seed}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn start_is_cleared() {
+    for seed in 0..16 {
+      let layout = generate(seed);
+      for cell in cells() {
+        if (cell - layout.start).length_squared() <= START_CLEARING * START_CLEARING {
+          assert!(!layout.is_wall(cell), "wall at {cell} with seed {seed}");
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn open_cells_are_reachable() {
+    for seed in 0..16 {
+      let layout = generate(seed);
+      let distances = layout.distances();
+      for cell in cells().filter(|cell| !layout.is_wall(*cell)) {
+        assert!(
+          distances[ArenaLayout::index(cell)].is_some(),
+          "unreachable cave at {cell} with seed {seed}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn spawners_are_open() {
+    for seed in 0..16 {
+      let layout = generate(seed);
+      assert!(!layout.spawners.is_empty());
+      for spawner in &layout.spawners {
+        assert!(
+          *spawner == layout.start || layout.wall_neighbours(*spawner) == 0,
+          "spawner in a wall at {spawner} with seed {seed}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn narrow_caves_spawn_at_start() {
+    let mut layout = generate(0);
+    layout.walls = cells().map(|cell| cell != layout.start).collect();
+    let distances = layout.distances();
+    assert_eq!(layout.place_spawners(&distances), vec![layout.start]);
+  }
+}
@@ -1,12 +1,31 @@
-use crate::{assets::UiAssets, game::enemy::DyingComponent, prelude::*};
+use crate::{
+  assets::UiAssets,
+  game::enemy::{DyingComponent, EnemyDeathCause},
+  prelude::*,
+};
 use bevy::{
   audio::{PlaybackMode, Volume},
-  window::PrimaryWindow,
+  utils::HashSet,
 };
 use bevy_rapier2d::prelude::*;
 use seldom_state::prelude::StateMachine;
 
-use super::Score;
+use super::{
+  input::PlayerInput,
+  mode::RunModifiers,
+  post_process::ScreenFlashEvent,
+  power_ups::{PowerUpEffects, PowerUpKind, WIDE_TRAIL_RADIUS},
+  score::{spawn_popup, ScoreEvent, ScoreSource},
+  time_scale::HitStopEvent,
+};
+
+/// Multi-kills of at least this many enemies flash the screen.
+const MULTI_KILL_FLASH_HITS: usize = 3;
+/// Strength of the flash for every kill from [`MULTI_KILL_FLASH_HITS`], up to 1.
+const MULTI_KILL_FLASH: f32 = 0.35;
+/// Multi-kills slow the game down for this long per kill, expressed in seconds.
+const MULTI_KILL_HIT_STOP: f32 = 0.03;
+const MULTI_KILL_HIT_STOP_SPEED: f32 = 0.1;
 
 #[derive(Component)]
 pub struct AttackComponent;
@@ -36,37 +55,56 @@ pub struct AttackTrail;
 #[derive(Component)]
 pub struct Cooldown(pub Timer);
 
+/// Enemies killed by an attack, accumulated until the attack is resolved.
+#[derive(Component, Default)]
+pub struct AttackHits(pub Vec<(Vec2, EnemyVariant)>);
+
+/// Sent when a shape is drawn, `valid` is false when it is too small or not closed.
+#[derive(Event)]
+pub struct AttackEvent {
+  pub valid: bool,
+}
+
+/// Sent once per shape when its attack kills some enemies.
+#[derive(Event)]
+pub struct AttackResolvedEvent {
+  /// The number of enemies killed.
+  pub hits: usize,
+  /// The center of the killed enemies.
+  pub position: Vec2,
+  /// The distinct variants of the killed enemies.
+  pub variants: Vec<EnemyVariant>,
+}
+
 pub struct AttackPlugin;
 
 impl Plugin for AttackPlugin {
   fn build(&self, app: &mut App) {
+    app.add_event::<AttackEvent>();
+    app.add_event::<AttackResolvedEvent>();
+
     app.add_systems(OnEnter(AppState::InGame), init_attack);
+    // The trail is sampled on fixed ticks, like the input driving it
     app.add_systems(
-      Update,
-      track_mouse_movement
-        .run_if(in_state(AppState::InGame))
-        .after(init_attack),
+      FixedUpdate,
+      (track_mouse_movement, check_attack)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
     );
+    // The fixed loop runs before `Update`, so the colliders spawned by `check_attack` are already there
     app.add_systems(
       Update,
-      check_attack
-        .run_if(in_state(AppState::InGame))
-        .after(track_mouse_movement),
-    );
-    app.add_systems(
-      Update,
-      check_for_collisions
-        .run_if(in_state(AppState::InGame))
-        .after(check_attack),
+      (check_for_collisions, resolve_attacks, multi_kill_feedback)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
     );
   }
 }
 
 fn init_attack(mut commands: Commands) {
-  let left_mouse_down =
-    |input: Res<ButtonInput<MouseButton>>| input.just_pressed(MouseButton::Left);
+  let left_mouse_down = |input: Res<PlayerInput>| input.frame.attack;
 
-  let left_mouse_up = |input: Res<ButtonInput<MouseButton>>| input.just_released(MouseButton::Left);
+  let left_mouse_up = |input: Res<PlayerInput>| !input.frame.attack;
 
   let state_machine = StateMachine::default()
     .trans::<Idle, _>(left_mouse_down, Attack)
@@ -83,21 +121,23 @@ fn init_attack(mut commands: Commands) {
 fn track_mouse_movement(
   mut commands: Commands,
   mut mouse_position: ResMut<AttackPositions>,
-  q_camera: Query<(&Camera, &GlobalTransform), With<Camera>>,
   query: Query<Entity, With<Attack>>,
-  q_windows: Query<&Window, With<PrimaryWindow>>,
+  input: Res<PlayerInput>,
+  modifiers: Res<RunModifiers>,
   attack_trail_query: Query<&AttackTrail, With<AudioSink>>,
   ui: Res<UiAssets>,
 ) {
   if let Ok(_entity) = query.get_single() {
-    let (camera, camera_transform) = q_camera.single();
-
-    if let Some(position) = q_windows
-      .single()
-      .cursor_position()
-      .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
-      .map(|ray| ray.origin.truncate())
-    {
+    if let Some(position) = input.frame.cursor() {
+      // Stop drawing when the trail runs out of ink
+      if let (Some(ink_limit), Some(last_position)) = (modifiers.ink_limit, mouse_position.0.last())
+      {
+        let used_ink = trail_length(&mouse_position.0) + last_position.distance(position);
+        if used_ink > ink_limit {
+          return;
+        }
+      }
+
       mouse_position.0.push(position);
 
       // sprite color should be red when the attack is not valid (area < 10k / not a closed shape)
@@ -132,11 +172,22 @@ fn track_mouse_movement(
   }
 }
 
+fn trail_length(positions: &[Vec2]) -> f32 {
+  positions
+    .iter()
+    .zip(positions.iter().skip(1))
+    .map(|(a, b)| a.distance(*b))
+    .sum()
+}
+
 pub fn check_attack(
   mut commands: Commands,
   mut positions: ResMut<AttackPositions>,
   query: Query<Entity, (With<AttackComponent>, With<Idle>)>,
   trail_query: Query<Entity, With<AttackTrail>>,
+  effects_query: Query<&PowerUpEffects>,
+  modifiers: Res<RunModifiers>,
+  mut attack_events: EventWriter<AttackEvent>,
 ) {
   if positions.0.len() < 2 {
     return;
@@ -155,21 +206,44 @@ pub fn check_attack(
 
     let mut points = vertices.clone();
 
-    if distance_between_points < SPRITE_SIZE * 2.0 && area >= MIN_ATTACK_AREA {
+    let valid = distance_between_points < SPRITE_SIZE * 2.0
+      && area >= MIN_ATTACK_AREA * modifiers.attack_area;
+    attack_events.send(AttackEvent { valid });
+
+    if valid {
       for i in 0..vertices.len() - 1 {
         let vertex = vertices[vertices.len() - 2 - i];
         points.push(vertices[i]);
         points.push(vertex);
       }
 
+      let wide_trail = effects_query
+        .iter()
+        .any(|effects| effects.is_active(PowerUpKind::WideTrail));
+
+      let collider = match wide_trail {
+        true => Collider::compound(
+          vertices
+            .iter()
+            .zip(vertices.iter().skip(1))
+            .map(|(a, b)| (Vect::ZERO, 0., Collider::capsule(*a, *b, WIDE_TRAIL_RADIUS)))
+            .collect(),
+        ),
+        false => Collider::polyline(points, None),
+      };
+
       commands.spawn((
         StateDespawnMarker,
         AttackTrailCollider,
-        Collider::polyline(points, None),
+        collider,
         CollisionGroups::new(ATTACK_TRAIL_GROUP, ENEMY_GROUP),
         ActiveCollisionTypes::all(),
         Sensor,
-        Cooldown(Timer::from_seconds(0.1, TimerMode::Once)),
+        Cooldown(Timer::from_seconds(
+          0.1 * modifiers.trail_lifetime,
+          TimerMode::Once,
+        )),
+        AttackHits::default(),
         CollidingEntities::default(),
         ActiveEvents::COLLISION_EVENTS,
       ));
@@ -185,31 +259,111 @@ pub fn check_attack(
 
 fn check_for_collisions(
   mut commands: Commands,
-  mut query: Query<(Entity, &CollidingEntities, &mut Cooldown), With<AttackTrailCollider>>,
-  enemies: Query<Entity, With<Enemy>>,
-  mut score: ResMut<Score>,
-  time: Res<Time>,
+  mut query: Query<(&CollidingEntities, &mut AttackHits), With<AttackTrailCollider>>,
+  enemies: Query<(Entity, &GlobalTransform, &Enemy), Without<DyingComponent>>,
 ) {
-  let mut enemies_killed: usize = 0;
+  // Enemies can touch several attacks in the same frame, but only die once
+  let mut killed = HashSet::new();
+
+  for (colliders, mut hits) in &mut query {
+    for (enemy_entity, transform, enemy) in &enemies {
+      if colliders.contains(enemy_entity) && killed.insert(enemy_entity) {
+        commands
+          .entity(enemy_entity)
+          .insert(DyingComponent(EnemyDeathCause::Attack));
+        hits
+          .0
+          .push((transform.translation().truncate(), enemy.variant()));
+      }
+    }
+  }
+}
 
-  for (collider_entity, colliders, mut cooldown) in &mut query {
+/// Once the attack collider expired, every enemy it touched is accounted for at once.
+fn resolve_attacks(
+  mut commands: Commands,
+  mut query: Query<(Entity, &AttackHits, &mut Cooldown), With<AttackTrailCollider>>,
+  mut resolved_events: EventWriter<AttackResolvedEvent>,
+  mut score_events: EventWriter<ScoreEvent>,
+  time: Res<Time>,
+) {
+  for (collider_entity, hits, mut cooldown) in &mut query {
     cooldown.0.tick(time.delta());
+    if !cooldown.0.finished() {
+      continue;
+    }
 
-    for enemy_entity in &enemies {
-      if colliders.contains(enemy_entity) {
-        commands.entity(enemy_entity).insert(DyingComponent);
-        commands.entity(collider_entity).despawn();
-        enemies_killed += 1;
-      }
+    commands.entity(collider_entity).despawn();
+
+    if hits.0.is_empty() {
+      continue;
     }
 
-    if cooldown.0.finished() {
-      // entity could have been despawned
-      if commands.get_entity(collider_entity).is_some() {
-        commands.entity(collider_entity).despawn();
+    let count = hits.0.len();
+    let position = hits.0.iter().map(|(position, _)| *position).sum::<Vec2>() / count as f32;
+
+    let mut variants = vec![];
+    for (_, variant) in hits.0.iter() {
+      if !variants.contains(variant) {
+        variants.push(*variant);
       }
     }
+
+    resolved_events.send(AttackResolvedEvent {
+      hits: count,
+      position,
+      variants,
+    });
+    score_events.send(ScoreEvent {
+      source: ScoreSource::Kill { hits: count },
+      position,
+    });
   }
+}
+
+fn multi_kill_feedback(
+  mut commands: Commands,
+  mut resolved_events: EventReader<AttackResolvedEvent>,
+  mut flash_events: EventWriter<ScreenFlashEvent>,
+  mut hit_stop_events: EventWriter<HitStopEvent>,
+  ui: Res<UiAssets>,
+) {
+  for event in resolved_events.read() {
+    if event.hits < 2 {
+      continue;
+    }
+
+    hit_stop_events.send(HitStopEvent {
+      duration: MULTI_KILL_HIT_STOP * event.hits as f32,
+      speed: MULTI_KILL_HIT_STOP_SPEED,
+    });
 
-  score.0 += enemies_killed.pow(3);
+    // Only the bigger multi-kills flash the screen, stronger with every kill
+    if event.hits >= MULTI_KILL_FLASH_HITS {
+      flash_events.send(ScreenFlashEvent(
+        MULTI_KILL_FLASH * (event.hits + 1 - MULTI_KILL_FLASH_HITS) as f32,
+      ));
+    }
+
+    spawn_popup(
+      &mut commands,
+      &ui,
+      event.position + Vec2::Y * SPRITE_SIZE,
+      format!("Multi-kill x{}", event.hits),
+    );
+
+    // The bigger the multi-kill, the higher the pitch
+    commands.spawn((
+      StateDespawnMarker,
+      AudioBundle {
+        source: ui.attack_sound.clone(),
+        settings: PlaybackSettings {
+          mode: PlaybackMode::Despawn,
+          volume: Volume::new(0.3),
+          speed: 1. + 0.15 * (event.hits - 1) as f32,
+          ..Default::default()
+        },
+      },
+    ));
+  }
 }
@@ -0,0 +1,280 @@
+use bevy::{
+  render::{
+    camera::{RenderTarget, ScalingMode, Viewport},
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages},
+    texture::{BevyDefault, ImageSampler},
+    view::RenderLayers,
+  },
+  transform::TransformSystem,
+  window::PrimaryWindow,
+};
+use bevy_ecs_ldtk::{
+  assets::{LdtkProject, LevelMetadataAccessor},
+  LevelEvent, LevelIid,
+};
+use bevy_rapier2d::{plugin::PhysicsSet, prelude::Velocity};
+
+use crate::prelude::*;
+
+/// How fast the camera catches up with its target, higher is snappier.
+const FOLLOW_SMOOTHING: f32 = 6.;
+/// How far ahead of the player the camera looks, expressed in seconds of movement.
+const LOOK_AHEAD_TIME: f32 = 0.3;
+const MAX_LOOK_AHEAD: f32 = SPRITE_SIZE * 2.;
+/// The camera jumps to its target instead of panning when it is further than this, e.g. after a teleport.
+const SNAP_DISTANCE: f32 = SPRITE_SIZE * 10.;
+/// Offset and rotation of the camera at full trauma.
+const MAX_SHAKE_OFFSET: f32 = 12.;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// Trauma lost every second.
+const TRAUMA_DECAY: f32 = 1.5;
+const SHAKE_FREQUENCY: f32 = 30.;
+
+/// The camera of the game, following the player.
+#[derive(Component, Default)]
+pub struct GameCamera {
+  /// Where the camera looks before the shake is applied.
+  focus: Vec2,
+  /// Shake intensity between 0 and 1, the shake grows with its square.
+  trauma: f32,
+}
+
+/// The window camera, showing the picture of the [`GameCamera`] upscaled in the letterboxed viewport.
+/// The UI is drawn by this camera too, so it is laid out within the same viewport.
+#[derive(Component)]
+pub struct ScreenCamera;
+
+/// The layer of the upscaled picture, so the game camera doesn't draw it again.
+const SCREEN_LAYER: usize = 1;
+
+/// The area the camera can show, usually the current level.
+/// An empty rectangle doesn't restrict the camera.
+#[derive(Resource, Default)]
+pub struct CameraBounds(pub Rect);
+
+/// Shake the camera, adding the given trauma between 0 and 1.
+#[derive(Event)]
+pub struct CameraShakeEvent(pub f32);
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<CameraBounds>();
+    app.add_event::<CameraShakeEvent>();
+
+    app.add_systems(OnEnter(AppState::InGame), spawn_camera);
+    app.add_systems(Update, letterbox.run_if(in_state(AppState::InGame)));
+    app.add_systems(
+      PostUpdate,
+      (ldtk_level_bounds, add_trauma, follow_player)
+        .chain()
+        .after(PhysicsSet::Writeback)
+        .before(TransformSystem::TransformPropagate)
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+/// The game camera renders the world in an image of the virtual resolution,
+/// so every sprite and effect lands on the virtual pixel grid.
+fn spawn_camera(
+  mut commands: Commands,
+  mut bounds: ResMut<CameraBounds>,
+  mut images: ResMut<Assets<Image>>,
+) {
+  *bounds = CameraBounds::default();
+
+  let mut image = Image::new_fill(
+    Extent3d {
+      width: VIRTUAL_WIDTH as u32,
+      height: VIRTUAL_HEIGHT as u32,
+      ..Default::default()
+    },
+    TextureDimension::D2,
+    &[0, 0, 0, 255],
+    TextureFormat::bevy_default(),
+    RenderAssetUsages::default(),
+  );
+  image.texture_descriptor.usage =
+    TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+  image.sampler = ImageSampler::nearest();
+  let image = images.add(image);
+
+  let projection = OrthographicProjection {
+    scaling_mode: ScalingMode::Fixed {
+      width: VIRTUAL_WIDTH,
+      height: VIRTUAL_HEIGHT,
+    },
+    ..Default::default()
+  };
+
+  commands.spawn((
+    StateDespawnMarker,
+    GameCamera::default(),
+    Camera2dBundle {
+      camera: Camera {
+        order: -1,
+        target: RenderTarget::Image(image.clone()),
+        ..Default::default()
+      },
+      transform: Transform::from_xyz(0., 0., CAMERA_Z_INDEX),
+      projection: projection.clone(),
+      ..Default::default()
+    },
+  ));
+
+  commands.spawn((
+    StateDespawnMarker,
+    ScreenCamera,
+    RenderLayers::layer(SCREEN_LAYER),
+    Camera2dBundle {
+      camera: Camera {
+        clear_color: ClearColorConfig::Custom(Color::BLACK),
+        ..Default::default()
+      },
+      projection,
+      ..Default::default()
+    },
+  ));
+
+  commands.spawn((
+    StateDespawnMarker,
+    RenderLayers::layer(SCREEN_LAYER),
+    SpriteBundle {
+      texture: image,
+      ..Default::default()
+    },
+  ));
+}
+
+/// Fit the viewport in the window with the largest whole scale of the virtual resolution,
+/// leaving bars around it. Windows smaller than the virtual resolution scale it down.
+fn letterbox(
+  mut camera_query: Query<&mut Camera, With<ScreenCamera>>,
+  window_query: Query<&Window, With<PrimaryWindow>>,
+) {
+  let (Ok(mut camera), Ok(window)) = (camera_query.get_single_mut(), window_query.get_single())
+  else {
+    return;
+  };
+
+  let window_size = window.physical_size();
+  let virtual_size = Vec2::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT);
+  let scale = (window_size.as_vec2() / virtual_size).min_element();
+  let scale = match scale >= 1. {
+    true => scale.floor(),
+    false => scale,
+  };
+
+  let physical_size = (virtual_size * scale)
+    .as_uvec2()
+    .max(UVec2::ONE)
+    .min(window_size);
+  let physical_position = (window_size - physical_size) / 2;
+
+  let viewport = camera.viewport.get_or_insert_with(Viewport::default);
+  if viewport.physical_size != physical_size || viewport.physical_position != physical_position {
+    viewport.physical_size = physical_size;
+    viewport.physical_position = physical_position;
+  }
+}
+
+/// Restrict the camera to the LDtk level once it is placed.
+fn ldtk_level_bounds(
+  mut level_events: EventReader<LevelEvent>,
+  mut bounds: ResMut<CameraBounds>,
+  level_query: Query<(&LevelIid, &GlobalTransform)>,
+  world_query: Query<&Handle<LdtkProject>>,
+  projects: Res<Assets<LdtkProject>>,
+) {
+  for event in level_events.read() {
+    let LevelEvent::Transformed(iid) = event else {
+      continue;
+    };
+
+    let Some(level) = world_query.iter().find_map(|handle| {
+      projects
+        .get(handle)
+        .and_then(|project| project.get_raw_level_by_iid(iid.get()))
+    }) else {
+      continue;
+    };
+
+    let Some((_, transform)) = level_query.iter().find(|(level_iid, _)| *level_iid == iid) else {
+      continue;
+    };
+
+    let min = transform.translation().truncate();
+    let size = Vec2::new(level.px_wid as f32, level.px_hei as f32);
+    bounds.0 = Rect::from_corners(min, min + size);
+  }
+}
+
+fn add_trauma(
+  mut shake_events: EventReader<CameraShakeEvent>,
+  mut camera_query: Query<&mut GameCamera>,
+) {
+  let Ok(mut camera) = camera_query.get_single_mut() else {
+    return;
+  };
+
+  for CameraShakeEvent(trauma) in shake_events.read() {
+    camera.trauma = (camera.trauma + trauma).min(1.);
+  }
+}
+
+pub fn follow_player(
+  mut camera_query: Query<(&mut GameCamera, &mut Transform, &OrthographicProjection)>,
+  player_query: Query<(&Transform, &Velocity), (With<Player>, Without<GameCamera>)>,
+  bounds: Res<CameraBounds>,
+  time: Res<Time>,
+) {
+  let (Ok((mut camera, mut transform, projection)), Ok((player_transform, velocity))) =
+    (camera_query.get_single_mut(), player_query.get_single())
+  else {
+    return;
+  };
+
+  let look_ahead = (velocity.linvel * LOOK_AHEAD_TIME).clamp_length_max(MAX_LOOK_AHEAD);
+  let mut target = player_transform.translation.truncate() + look_ahead;
+
+  // Keep the view within the bounds, centered when the bounds are smaller than the view
+  if !bounds.0.is_empty() {
+    let half_view = projection.area.half_size();
+    let min = bounds.0.min + half_view;
+    let max = bounds.0.max - half_view;
+    target = Vec2::new(
+      clamp_or_center(target.x, min.x, max.x),
+      clamp_or_center(target.y, min.y, max.y),
+    );
+  }
+
+  camera.focus = match camera.focus.distance(target) > SNAP_DISTANCE {
+    true => target,
+    false => camera.focus.lerp(
+      target,
+      1. - (-FOLLOW_SMOOTHING * time.delta_seconds()).exp(),
+    ),
+  };
+
+  camera.trauma = (camera.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.);
+  let shake = camera.trauma * camera.trauma;
+  let t = time.elapsed_seconds() * SHAKE_FREQUENCY;
+  let offset = Vec2::new((t * 1.3).sin(), (t * 1.7 + 1.).sin()) * MAX_SHAKE_OFFSET * shake;
+  let angle = (t * 0.9 + 2.).sin() * MAX_SHAKE_ANGLE * shake;
+
+  // Whole pixels only, so the world doesn't shimmer while the camera pans
+  transform.translation = (camera.focus + offset)
+    .round()
+    .extend(transform.translation.z);
+  transform.rotation = Quat::from_rotation_z(angle);
+}
+
+fn clamp_or_center(value: f32, min: f32, max: f32) -> f32 {
+  match min <= max {
+    true => value.clamp(min, max),
+    false => (min + max) / 2.,
+  }
+}
@@ -0,0 +1,203 @@
+use bevy::state::state::StateTransitionEvent;
+
+use crate::{assets::UiAssets, game::lighting::is_night_cycle, prelude::*};
+
+use super::{Cycle, Meteor};
+
+/// How long the transition banner stays on screen, expressed in seconds.
+const BANNER_DURATION: f32 = 1.6;
+/// Portion of [`BANNER_DURATION`] spent zooming in.
+const BANNER_ZOOM_IN: f32 = 0.15;
+/// Portion of [`BANNER_DURATION`] spent fading out.
+const BANNER_FADE_OUT: f32 = 0.35;
+
+#[derive(Component)]
+struct CycleIndexText;
+
+/// Shows the remaining time in [`CycleState::Standard`]
+/// and the remaining meteors in [`CycleState::Meteors`].
+#[derive(Component)]
+struct CycleStatusText;
+
+#[derive(Component)]
+struct CycleBanner {
+  timer: Timer,
+}
+
+pub struct CycleHudPlugin;
+
+impl Plugin for CycleHudPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(AppState::InGame), setup_cycle_hud);
+    app.add_systems(
+      Update,
+      (update_cycle_hud, spawn_cycle_banner, animate_cycle_banner)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn text_style(ui: &UiAssets, font_size: f32) -> TextStyle {
+  TextStyle {
+    font: ui.font_mono.clone(),
+    font_size,
+    color: Color::WHITE,
+  }
+}
+
+fn setup_cycle_hud(mut commands: Commands, ui: Res<UiAssets>) {
+  commands
+    .spawn((
+      StateDespawnMarker,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          top: Val::Px(54.),
+          right: Val::Px(16.),
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::End,
+          row_gap: Val::Px(4.),
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn((
+        CycleIndexText,
+        TextBundle::from_section(cycle_title(1), text_style(&ui, 24.)),
+      ));
+      parent.spawn((
+        CycleStatusText,
+        TextBundle::from_section(String::new(), text_style(&ui, 20.)),
+      ));
+    });
+
+  spawn_banner(&mut commands, &ui, cycle_title(1));
+}
+
+fn cycle_title(index: usize) -> String {
+  match is_night_cycle(index) {
+    true => format!("Night {:02}", index),
+    false => format!("Cycle {:02}", index),
+  }
+}
+
+fn update_cycle_hud(
+  state: Res<State<CycleState>>,
+  cycle_query: Query<&Cycle>,
+  meteors_query: Query<(), With<Meteor>>,
+  mut index_query: Query<&mut Text, (With<CycleIndexText>, Without<CycleStatusText>)>,
+  mut status_query: Query<&mut Text, (With<CycleStatusText>, Without<CycleIndexText>)>,
+) {
+  let Ok(cycle) = cycle_query.get_single() else {
+    return;
+  };
+
+  let title = cycle_title(cycle.index);
+  let status = match state.get() {
+    CycleState::Standard => format!("{:.1}s", cycle.start.remaining_secs()),
+    CycleState::Meteors => format!("Meteors {}", cycle.meteors + meteors_query.iter().len()),
+  };
+
+  // Only touch the text when its value changes, to avoid re-layouting the UI every frame
+  for mut text in &mut index_query {
+    if text.sections[0].value != title {
+      text.sections[0].value.clone_from(&title);
+    }
+  }
+
+  for mut text in &mut status_query {
+    if text.sections[0].value != status {
+      text.sections[0].value.clone_from(&status);
+    }
+  }
+}
+
+fn spawn_cycle_banner(
+  mut commands: Commands,
+  mut transitions: EventReader<StateTransitionEvent<CycleState>>,
+  cycle_query: Query<&Cycle>,
+  banner_query: Query<Entity, With<CycleBanner>>,
+  ui: Res<UiAssets>,
+) {
+  for transition in transitions.read() {
+    // Same state transitions are sent too, but they don't show a banner
+    if transition.exited == transition.entered {
+      continue;
+    }
+
+    let Ok(cycle) = cycle_query.get_single() else {
+      continue;
+    };
+
+    let message = match transition.entered {
+      Some(CycleState::Standard) => cycle_title(cycle.index),
+      Some(CycleState::Meteors) => "Take Cover!".to_string(),
+      None => continue,
+    };
+
+    // Only one banner at a time, the newest one wins
+    for entity in &banner_query {
+      commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_banner(&mut commands, &ui, message);
+  }
+}
+
+fn spawn_banner(commands: &mut Commands, ui: &UiAssets, message: String) {
+  commands
+    .spawn((
+      StateDespawnMarker,
+      CycleBanner {
+        timer: Timer::from_seconds(BANNER_DURATION, TimerMode::Once),
+      },
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.),
+          position_type: PositionType::Absolute,
+          top: Val::Percent(25.),
+          justify_content: JustifyContent::Center,
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(message, text_style(ui, 48.)));
+    });
+}
+
+fn animate_cycle_banner(
+  mut commands: Commands,
+  mut banner_query: Query<(Entity, &mut CycleBanner, &mut Transform, &Children)>,
+  mut text_query: Query<&mut Text>,
+  time: Res<Time>,
+) {
+  for (entity, mut banner, mut transform, children) in &mut banner_query {
+    banner.timer.tick(time.delta());
+
+    if banner.timer.finished() {
+      commands.entity(entity).despawn_recursive();
+      continue;
+    }
+
+    let progress = banner.timer.fraction();
+
+    // Zoom in from 1.5x, then hold
+    let zoom = (progress / BANNER_ZOOM_IN).min(1.0);
+    transform.scale = Vec3::splat(1.5 - zoom * 0.5);
+
+    // Fade out at the end
+    let fade_start = 1.0 - BANNER_FADE_OUT;
+    let alpha = 1.0 - ((progress - fade_start) / BANNER_FADE_OUT).clamp(0.0, 1.0);
+
+    for child in children {
+      if let Ok(mut text) = text_query.get_mut(*child) {
+        text.sections[0].style.color.set_alpha(alpha);
+      }
+    }
+  }
+}
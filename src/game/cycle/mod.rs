@@ -1,9 +1,27 @@
 use crate::prelude::*;
 use bevy::sprite::MaterialMesh2dBundle;
+use bevy_rapier2d::prelude::RapierContext;
+use hud::CycleHudPlugin;
 use rand::Rng;
 use seldom_state::prelude::*;
 
-use super::Score;
+use super::{
+  camera::CameraShakeEvent,
+  indicators::Threat,
+  lighting::LightSource,
+  mode::{init_run_mode, RunModifiers},
+  power_ups::{PowerUpEffects, PowerUpKind},
+  rng::{init_rng, GameRng, RngStream},
+  score::{ScoreEvent, ScoreSource},
+  tiles::{cover_at, cover_between, CoverHitEvent, MeteorZone},
+};
+
+mod hud;
+
+/// Camera shake when a meteor hits the ground.
+const METEOR_TRAUMA: f32 = 0.3;
+/// How far the glow of a meteor reaches at night, growing as it falls.
+const METEOR_LIGHT_RADIUS: f32 = SPRITE_SIZE * 3.;
 
 #[derive(Component)]
 struct Meteor;
@@ -43,13 +61,31 @@ struct Cycle {
 #[derive(Resource)]
 struct MeteorSpawnDelay(Timer);
 
+/// Sent when a meteor hits the ground.
+#[derive(Event)]
+pub struct MeteorImpactEvent {
+  pub hit_player: bool,
+}
+
+/// Sent when all the meteors of a cycle fell, with the index of the completed cycle.
+#[derive(Event)]
+pub struct CycleCompletedEvent {
+  pub index: usize,
+}
+
 pub struct CyclePlugin;
 
 impl Plugin for CyclePlugin {
   fn build(&self, app: &mut App) {
     app.init_state::<CycleState>();
+    app.add_event::<MeteorImpactEvent>();
+    app.add_event::<CycleCompletedEvent>();
+    app.add_plugins(CycleHudPlugin);
 
-    app.add_systems(OnEnter(AppState::InGame), init_cycle);
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      init_cycle.after(init_rng).after(init_run_mode),
+    );
     app.add_systems(
       Update,
       check_cycle_state
@@ -75,6 +111,11 @@ impl Plugin for CyclePlugin {
   }
 }
 
+/// The number of meteors falling in the cycle with the given index.
+fn cycle_meteors(index: usize, modifiers: &RunModifiers) -> usize {
+  ((CYCLE_WEIGHT * index) as f32 * modifiers.meteor_density).round() as usize
+}
+
 fn check_cycle_state(
   mut next_state: ResMut<NextState<CycleState>>,
   time: Res<Time>,
@@ -89,18 +130,25 @@ fn check_cycle_state(
   }
 }
 
-fn init_cycle(mut commands: Commands, mut next_state: ResMut<NextState<CycleState>>) {
+pub fn init_cycle(
+  mut commands: Commands,
+  mut next_state: ResMut<NextState<CycleState>>,
+  mut rng: ResMut<GameRng>,
+  modifiers: Res<RunModifiers>,
+) {
   commands.spawn((
     StateDespawnMarker,
     Cycle {
       start: Timer::from_seconds(CYCLE_DURATION, TimerMode::Once),
-      meteors: CYCLE_WEIGHT,
+      meteors: cycle_meteors(1, &modifiers),
       index: 1,
     },
   ));
 
   commands.insert_resource(MeteorSpawnDelay(Timer::from_seconds(
-    rand::thread_rng().gen_range(METEOR_SPAWN_DELAY..METEOR_SPAWN_DELAY + 0.1),
+    rng
+      .stream(RngStream::Cycle)
+      .gen_range(METEOR_SPAWN_DELAY..METEOR_SPAWN_DELAY + 0.1),
     TimerMode::Once,
   )));
   next_state.set(CycleState::Standard);
@@ -110,15 +158,21 @@ fn spawn_meteor(
   mut commands: Commands,
   mut query: Query<&mut Cycle>,
   mut next_state: ResMut<NextState<CycleState>>,
-  mut score: ResMut<Score>,
+  mut score_events: EventWriter<ScoreEvent>,
+  mut completed_events: EventWriter<CycleCompletedEvent>,
   player_query: Query<&Transform, With<Player>>,
+  zone_query: Query<(&MeteorZone, &GlobalTransform)>,
   mut meteor_spawn_delay: ResMut<MeteorSpawnDelay>,
   mut meshes: ResMut<Assets<Mesh>>,
   mut materials: ResMut<Assets<ColorMaterial>>,
   meteors_query: Query<Entity, With<Meteor>>,
+  mut rng: ResMut<GameRng>,
+  modifiers: Res<RunModifiers>,
   time: Res<Time>,
 ) {
+  let rng = rng.stream(RngStream::Meteor);
   let mut cycle = query.get_single_mut().unwrap();
+  let player_position = player_query.get_single().unwrap().translation.truncate();
   meteor_spawn_delay.0.tick(time.delta());
 
   if meteor_spawn_delay.0.finished() {
@@ -135,9 +189,13 @@ fn spawn_meteor(
   if cycle.meteors == 0 && meteors_query.iter().len() == 0 {
     next_state.set(CycleState::Standard);
     cycle.start = Timer::from_seconds(CYCLE_DURATION, TimerMode::Once);
+    completed_events.send(CycleCompletedEvent { index: cycle.index });
+    score_events.send(ScoreEvent {
+      source: ScoreSource::CycleCompleted { index: cycle.index },
+      position: player_position,
+    });
     cycle.index += 1;
-    cycle.meteors = CYCLE_WEIGHT * cycle.index;
-    score.0 += cycle.index.pow(3);
+    cycle.meteors = cycle_meteors(cycle.index, &modifiers);
   };
 
   let is_on_ground = |In(entity): In<Entity>, query: Query<&Transform>| {
@@ -151,18 +209,28 @@ fn spawn_meteor(
   let state_machine = state_machine.set_trans_logging(true);
 
   let meteor_transform: Transform = {
-    let player_position = player_query.get_single().unwrap().translation.truncate();
-
-    let distance = rand::thread_rng().gen_range(0.0..100.0);
-    let angle = rand::random::<f32>() * std::f32::consts::PI * 2.0;
-    let x = player_position.x + angle.cos() * distance;
-    let y = player_position.y + angle.sin() * distance;
-    Transform::from_xyz(x, y, 25.)
+    let distance = rng.gen_range(0.0..100.0);
+    let angle = rng.gen::<f32>() * std::f32::consts::PI * 2.0;
+    let target = player_position + Vec2::from_angle(angle) * distance;
+
+    // Meteors falling outside of the zones of the level land in the closest one
+    let target = zone_query
+      .iter()
+      .map(|(zone, transform)| zone.clamp(transform.translation().truncate(), target))
+      .min_by(|a, b| {
+        a.distance_squared(target)
+          .total_cmp(&b.distance_squared(target))
+      })
+      .unwrap_or(target);
+
+    Transform::from_translation(target.extend(25.))
   };
 
   commands.spawn((
     StateDespawnMarker,
     Meteor,
+    Threat::Meteor,
+    LightSource::new(METEOR_LIGHT_RADIUS, colors::RED_400),
     state_machine,
     MaterialMesh2dBundle {
       mesh: meshes.add(Circle::new(SPRITE_SIZE / 2.)).into(),
@@ -171,7 +239,7 @@ fn spawn_meteor(
       ..default()
     },
     Falling,
-    FallSpeed(rand::thread_rng().gen_range(0.3..0.5)),
+    FallSpeed(rng.gen_range(0.3..0.5)),
   ));
 
   cycle.meteors -= 1;
@@ -202,22 +270,53 @@ fn falling_meteor(
 fn check_impact(
   mut commands: Commands,
   impact_query: Query<(Entity, &Transform), With<Impact>>,
-  player_query: Query<&Transform, With<Player>>,
-  mut score: ResMut<Score>,
-  mut next_state: ResMut<NextState<AppState>>,
+  mut player_query: Query<(&Transform, &mut PowerUpEffects), With<Player>>,
+  mut score_events: EventWriter<ScoreEvent>,
+  mut impact_events: EventWriter<MeteorImpactEvent>,
+  mut hit_events: EventWriter<PlayerHitEvent>,
+  mut cover_events: EventWriter<CoverHitEvent>,
+  mut shake_events: EventWriter<CameraShakeEvent>,
+  rapier_context: Res<RapierContext>,
 ) {
   for (entity, transform) in &mut impact_query.iter() {
-    let player_transform = player_query.single();
+    let (player_transform, mut effects) = player_query.single_mut();
     let player_position = player_transform.translation.truncate();
     let meteor_position = transform.translation.truncate();
 
     // This doesn't work as expected. + now it seems it does, but to be investigated
-    if player_position.distance(meteor_position) < SPRITE_SIZE {
-      next_state.set(AppState::GameOver);
+    let mut hit_player = player_position.distance(meteor_position) < SPRITE_SIZE;
+
+    // Cover takes the hit instead of the player, or the meteor landed on it
+    let cover = match hit_player {
+      true => cover_between(&rapier_context, meteor_position, player_position),
+      false => None,
+    }
+    .or_else(|| cover_at(&rapier_context, meteor_position));
+    if let Some(cover) = cover {
+      cover_events.send(CoverHitEvent(cover));
+      hit_player = false;
+    }
+
+    // The shield absorbs a single meteor
+    if hit_player && effects.is_active(PowerUpKind::MeteorShield) {
+      effects.consume(PowerUpKind::MeteorShield);
+      hit_player = false;
+    }
+
+    if hit_player {
+      hit_events.send(PlayerHitEvent {
+        cause: DeathCause::Meteor,
+      });
     } else {
-      score.0 += 3;
+      score_events.send(ScoreEvent {
+        source: ScoreSource::MeteorDodged,
+        position: meteor_position,
+      });
     }
 
+    impact_events.send(MeteorImpactEvent { hit_player });
+    shake_events.send(CameraShakeEvent(METEOR_TRAUMA));
+
     commands.entity(entity).despawn();
   }
 }
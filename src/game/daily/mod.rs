@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage};
+
+use super::{
+  input::InputSource,
+  mode::{GameMode, RunModifiers},
+  rng::mix_seed,
+  Score,
+};
+
+const DAILY_BEST_KEY: &str = "daily";
+
+/// The best score of the daily challenge.
+/// Only the score of the last played day is kept.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct DailyBest {
+  pub date: Date,
+  pub score: usize,
+}
+
+impl DailyBest {
+  /// The best score of the given day, if it was played.
+  pub fn of(&self, date: Date) -> Option<usize> {
+    (self.date == date).then_some(self.score)
+  }
+}
+
+/// Whether the last run beat the previous best score of its daily challenge.
+#[derive(Resource, Default)]
+pub struct NewDailyBest(pub bool);
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+  fn build(&self, app: &mut App) {
+    if let Some(best) = storage::load::<DailyBest>(DAILY_BEST_KEY) {
+      app.insert_resource(best);
+    }
+    app.init_resource::<NewDailyBest>();
+
+    app.add_systems(OnExit(AppState::InGame), save_daily_best);
+  }
+}
+
+/// The seed of the daily challenge of the given date.
+///
+/// The daily challenge is derived with [`mix_seed`] only, so a `rand` update
+/// doesn't change the seed, level or modifiers of any day.
+pub fn seed(date: Date) -> u64 {
+  let day = (date.year as u64) * 10_000 + (date.month as u64) * 100 + date.day as u64;
+  mix_seed(day, 1)
+}
+
+/// The level of the daily challenge of the given date, out of `count` levels.
+pub fn level(date: Date, count: usize) -> usize {
+  (seed(date) % count as u64) as usize
+}
+
+/// The modifiers of the daily challenge of the given date.
+pub fn modifiers(date: Date) -> RunModifiers {
+  let seed = seed(date);
+  // A value in 0..1 out of the top 24 bits, which fit exactly in a f32
+  let unit = |index: u64| (mix_seed(seed, index) >> 40) as f32 / (1 << 24) as f32;
+
+  RunModifiers {
+    enemy_speed: 1. + unit(1) * 0.5,
+    meteor_density: 1. + unit(2),
+    ink_limit: Some((6 + mix_seed(seed, 3) % 7) as f32 * 100.),
+    ..Default::default()
+  }
+}
+
+/// A short description of the modifiers, shown in the menus.
+pub fn describe(modifiers: &RunModifiers) -> String {
+  let ink = match modifiers.ink_limit {
+    Some(ink_limit) => format!("{:.0}", ink_limit),
+    None => "unlimited".to_string(),
+  };
+  format!(
+    "Enemies x{:.1} - Meteors x{:.1} - Ink {}",
+    modifiers.enemy_speed, modifiers.meteor_density, ink
+  )
+}
+
+fn save_daily_best(
+  mut commands: Commands,
+  mut new_best: ResMut<NewDailyBest>,
+  input_source: Res<InputSource>,
+  mode: Res<GameMode>,
+  score: Res<Score>,
+  best: Option<Res<DailyBest>>,
+) {
+  new_best.0 = false;
+
+  // Replays are not runs
+  if *input_source == InputSource::Replay {
+    return;
+  }
+
+  let GameMode::Daily(date) = *mode else {
+    return;
+  };
+
+  if best
+    .and_then(|best| best.of(date))
+    .is_some_and(|best| best >= score.0)
+  {
+    return;
+  }
+
+  new_best.0 = true;
+
+  let best = DailyBest {
+    date,
+    score: score.0,
+  };
+
+  storage::save(DAILY_BEST_KEY, &best);
+  commands.insert_resource(best);
+}
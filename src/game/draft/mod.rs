@@ -0,0 +1,366 @@
+use bevy::ui::FocusPolicy;
+use bevy_rapier2d::plugin::RapierConfiguration;
+use rand::seq::index;
+use serde::Deserialize;
+
+use crate::{
+  app_state::InGameSubState,
+  assets::{DataAsset, UiAssets},
+  prelude::*,
+};
+
+use super::{
+  cycle::CycleCompletedEvent,
+  input::{InputSource, PendingInput},
+  mode::RunModifiers,
+  rng::{GameRng, RngStream},
+};
+
+/// The number of upgrades offered at the end of a cycle.
+const DRAFT_SIZE: usize = 3;
+
+/// A change to one of the [`RunModifiers`], multiplying it by the given value.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum DraftEffect {
+  PlayerSpeed(f32),
+  DodgeCooldown(f32),
+  AttackArea(f32),
+  TrailLifetime(f32),
+  EnemySpeed(f32),
+  EnemyChargeTime(f32),
+  MeteorDensity(f32),
+}
+
+impl DraftEffect {
+  fn apply(&self, modifiers: &mut RunModifiers) {
+    match *self {
+      DraftEffect::PlayerSpeed(value) => modifiers.player_speed *= value,
+      DraftEffect::DodgeCooldown(value) => modifiers.dodge_cooldown *= value,
+      DraftEffect::AttackArea(value) => modifiers.attack_area *= value,
+      DraftEffect::TrailLifetime(value) => modifiers.trail_lifetime *= value,
+      DraftEffect::EnemySpeed(value) => modifiers.enemy_speed *= value,
+      DraftEffect::EnemyChargeTime(value) => modifiers.enemy_charge_time *= value,
+      DraftEffect::MeteorDensity(value) => modifiers.meteor_density *= value,
+    }
+  }
+}
+
+/// An upgrade that can be picked between two cycles.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DraftCard {
+  pub title: String,
+  pub description: String,
+  pub effects: Vec<DraftEffect>,
+}
+
+/// Every upgrade that can be offered, loaded from `assets/data/draft.ron`.
+#[derive(Resource, Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct DraftPool(pub Vec<DraftCard>);
+
+impl DataAsset for DraftPool {}
+
+/// The cards of the [`DraftPool`] offered in the current draft.
+#[derive(Resource, Default)]
+pub struct DraftOffer(pub Vec<usize>);
+
+/// Sent when a card of the [`DraftOffer`] is picked, with its index in the offer.
+#[derive(Event)]
+pub struct DraftChosenEvent(pub usize);
+
+/// Marker for the entities of the draft screen.
+#[derive(Component)]
+struct DraftScreen;
+
+#[derive(Component)]
+struct DraftCardButton(usize);
+
+pub struct DraftPlugin;
+
+impl Plugin for DraftPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<DraftOffer>();
+    app.add_event::<DraftChosenEvent>();
+
+    app.add_systems(
+      PostUpdate,
+      open_draft
+        .run_if(in_state(InGameSubState::None))
+        .run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(
+      OnEnter(InGameSubState::Draft),
+      (pause_gameplay, setup_draft_screen),
+    );
+    app.add_systems(
+      Update,
+      (
+        draft_card_on_press.run_if(resource_equals(InputSource::Live)),
+        apply_draft_choice,
+      )
+        .chain()
+        .run_if(in_state(InGameSubState::Draft)),
+    );
+    app.add_systems(
+      OnExit(InGameSubState::Draft),
+      (resume_gameplay, despawn_all_recursive::<DraftScreen>),
+    );
+  }
+}
+
+/// Offer a draft when a cycle is completed.
+fn open_draft(
+  mut completed_events: EventReader<CycleCompletedEvent>,
+  mut offer: ResMut<DraftOffer>,
+  mut next_state: ResMut<NextState<InGameSubState>>,
+  mut rng: ResMut<GameRng>,
+  pool: Res<DraftPool>,
+) {
+  if completed_events.read().count() == 0 || pool.0.is_empty() {
+    return;
+  }
+
+  let amount = DRAFT_SIZE.min(pool.0.len());
+  offer.0 = index::sample(rng.stream(RngStream::Draft), pool.0.len(), amount).into_vec();
+  next_state.set(InGameSubState::Draft);
+}
+
+/// Virtual time and physics are stopped, so every gameplay system stands still.
+fn pause_gameplay(mut time: ResMut<Time<Virtual>>, mut rapier: ResMut<RapierConfiguration>) {
+  time.pause();
+  rapier.physics_pipeline_active = false;
+}
+
+fn resume_gameplay(
+  mut time: ResMut<Time<Virtual>>,
+  mut rapier: ResMut<RapierConfiguration>,
+  mut pending_input: ResMut<PendingInput>,
+) {
+  time.unpause();
+  rapier.physics_pipeline_active = true;
+  // The clicks on the draft screen are not attacks
+  pending_input.take();
+}
+
+fn setup_draft_screen(
+  mut commands: Commands,
+  ui: Res<UiAssets>,
+  offer: Res<DraftOffer>,
+  pool: Res<DraftPool>,
+) {
+  commands
+    .spawn((
+      StateDespawnMarker,
+      DraftScreen,
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.),
+          height: Val::Percent(100.),
+          position_type: PositionType::Absolute,
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          row_gap: Val::Px(32.),
+          ..Default::default()
+        },
+        background_color: colors::PRIMARY_900.with_alpha(0.8).into(),
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
+        ..Default::default()
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Choose an upgrade",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 48.,
+        },
+      ));
+
+      parent
+        .spawn(NodeBundle {
+          style: Style {
+            column_gap: Val::Px(24.),
+            ..Default::default()
+          },
+          ..Default::default()
+        })
+        .with_children(|parent| {
+          for (position, card) in offer
+            .0
+            .iter()
+            .filter_map(|index| pool.0.get(*index))
+            .enumerate()
+          {
+            parent
+              .spawn((
+                DraftCardButton(position),
+                ButtonBundle {
+                  style: Style {
+                    width: Val::Px(220.),
+                    padding: UiRect::all(Val::Px(16.)),
+                    border: UiRect::all(Val::Px(2.)),
+                    ..Default::default()
+                  },
+                  border_color: colors::PRIMARY_300.into(),
+                  background_color: colors::PRIMARY_700.into(),
+                  ..Default::default()
+                },
+              ))
+              .with_children(|parent| {
+                parent.spawn(
+                  TextBundle::from_sections([
+                    TextSection::new(
+                      format!("{}\n\n", card.title),
+                      TextStyle {
+                        font: ui.font_sans.clone(),
+                        color: colors::PRIMARY_100,
+                        font_size: 24.,
+                      },
+                    ),
+                    TextSection::new(
+                      card.description.clone(),
+                      TextStyle {
+                        font: ui.font_sans.clone(),
+                        color: colors::PRIMARY_300,
+                        font_size: 16.,
+                      },
+                    ),
+                  ])
+                  .with_text_justify(JustifyText::Center),
+                );
+              });
+          }
+        });
+    });
+}
+
+fn draft_card_on_press(
+  mut query: Query<(&Interaction, &DraftCardButton, &mut BorderColor), Changed<Interaction>>,
+  mut chosen_events: EventWriter<DraftChosenEvent>,
+) {
+  for (interaction, button, mut border_color) in &mut query {
+    match interaction {
+      Interaction::Pressed => {
+        chosen_events.send(DraftChosenEvent(button.0));
+      }
+      Interaction::Hovered => {
+        *border_color = colors::RED_400.into();
+      }
+      Interaction::None => {
+        *border_color = colors::PRIMARY_300.into();
+      }
+    }
+  }
+}
+
+pub fn apply_draft_choice(
+  mut chosen_events: EventReader<DraftChosenEvent>,
+  mut modifiers: ResMut<RunModifiers>,
+  mut next_state: ResMut<NextState<InGameSubState>>,
+  offer: Res<DraftOffer>,
+  pool: Res<DraftPool>,
+) {
+  // A single card is picked per draft
+  let Some(DraftChosenEvent(position)) = chosen_events.read().next() else {
+    return;
+  };
+
+  if let Some(card) = offer.0.get(*position).and_then(|index| pool.0.get(*index)) {
+    for effect in card.effects.iter() {
+      effect.apply(&mut modifiers);
+    }
+  }
+
+  chosen_events.clear();
+  next_state.set(InGameSubState::None);
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::system::RunSystemOnce;
+
+  use super::*;
+
+  /// Five cards, the n-th one multiplying the player speed by n.
+  fn draft_world(seed: u64) -> World {
+    let cards = (1..=5)
+      .map(|speed| DraftCard {
+        title: format!("Speed {speed}"),
+        description: String::new(),
+        effects: vec![DraftEffect::PlayerSpeed(speed as f32)],
+      })
+      .collect();
+
+    let mut world = World::new();
+    world.insert_resource(DraftPool(cards));
+    world.insert_resource(GameRng::new(seed));
+    world.init_resource::<DraftOffer>();
+    world.init_resource::<RunModifiers>();
+    world.init_resource::<NextState<InGameSubState>>();
+    world.init_resource::<Events<CycleCompletedEvent>>();
+    world.init_resource::<Events<DraftChosenEvent>>();
+    world
+  }
+
+  fn is_pending(world: &World, state: InGameSubState) -> bool {
+    matches!(world.resource::<NextState<InGameSubState>>(), NextState::Pending(next) if *next == state)
+  }
+
+  #[test]
+  fn no_draft_during_cycle() {
+    let mut world = draft_world(0);
+    world.run_system_once(open_draft);
+
+    assert!(world.resource::<DraftOffer>().0.is_empty());
+    assert!(!is_pending(&world, InGameSubState::Draft));
+  }
+
+  #[test]
+  fn offers_distinct_cards() {
+    let mut world = draft_world(0);
+    world.send_event(CycleCompletedEvent { index: 1 });
+    world.run_system_once(open_draft);
+
+    let mut offer = world.resource::<DraftOffer>().0.clone();
+    assert!(is_pending(&world, InGameSubState::Draft));
+    assert_eq!(offer.len(), DRAFT_SIZE);
+    assert!(offer.iter().all(|index| *index < 5));
+    offer.sort();
+    offer.dedup();
+    assert_eq!(offer.len(), DRAFT_SIZE);
+  }
+
+  #[test]
+  fn same_seed_same_offer() {
+    let offer = |seed| {
+      let mut world = draft_world(seed);
+      world.send_event(CycleCompletedEvent { index: 1 });
+      world.run_system_once(open_draft);
+      world.resource::<DraftOffer>().0.clone()
+    };
+
+    assert_eq!(offer(7), offer(7));
+  }
+
+  #[test]
+  fn applies_a_single_card() {
+    let mut world = draft_world(0);
+    world.insert_resource(DraftOffer(vec![4, 1, 0]));
+    world.send_event(DraftChosenEvent(1));
+    world.send_event(DraftChosenEvent(0));
+    world.run_system_once(apply_draft_choice);
+
+    // The second card of the offer is the second of the pool
+    assert_eq!(world.resource::<RunModifiers>().player_speed, 2.);
+    assert!(is_pending(&world, InGameSubState::None));
+  }
+
+  #[test]
+  fn bundled_pool_fills_a_draft() {
+    let pool: DraftPool = ron::from_str(include_str!("../../../assets/data/draft.ron")).unwrap();
+    assert!(pool.0.len() >= DRAFT_SIZE);
+  }
+}
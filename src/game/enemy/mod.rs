@@ -4,6 +4,7 @@ use bevy::{
   prelude::*,
   sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
+use bevy_ecs_ldtk::LevelEvent;
 use bevy_particle_systems::Playing;
 use bevy_rapier2d::{prelude::*, rapier::prelude::CollisionEventFlags};
 use effects::{make_attack_effect, make_dead_enemy_effect, make_dirt_effect};
@@ -13,21 +14,47 @@ use sprite::get_idle_animation;
 
 use crate::{assets::UiAssets, prelude::*};
 
-use super::common::{
-  animations::{animate_sprite, AnimationIndices},
-  tick_despawn_timer, DespawnTimer,
+use super::{
+  camera::CameraShakeEvent,
+  common::{
+    animations::{animate_sprite, AnimationIndices},
+    tick_despawn_timer, DespawnTimer,
+  },
+  indicators::Threat,
+  lighting::LightSource,
+  mode::RunModifiers,
+  rng::{GameRng, RngStream},
+  score::{ScoreEvent, ScoreSource},
+  tiles::{cover_between, terrain_at, Cover, CoverHitEvent, EnemySpawner, Terrain},
 };
 
 mod effects;
 mod sprite;
 
-#[derive(Clone)]
-enum EnemyVariant {
+/// Enemies never spawn closer to the player than this.
+const MIN_SPAWN_DISTANCE: f32 = 100.;
+/// Without a spawner far enough, enemies spawn around the player up to this distance.
+const MAX_SPAWN_DISTANCE: f32 = 200.;
+/// Camera shake when an enemy delivers its attack.
+const DELIVERY_TRAUMA: f32 = 0.15;
+/// Enemies within this distance of the player are knocked back by a dodge.
+const KNOCKBACK_RADIUS: f32 = SPRITE_SIZE * 3.;
+/// How fast a knocked back enemy is pushed away, expressed in pixels per second.
+const KNOCKBACK_SPEED: f32 = 200.;
+/// How long a knockback lasts, expressed in seconds.
+const KNOCKBACK_DURATION: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyVariant {
   Aqua,
   Red,
   Green,
 }
 
+impl EnemyVariant {
+  pub const ALL: [EnemyVariant; 3] = [EnemyVariant::Aqua, EnemyVariant::Red, EnemyVariant::Green];
+}
+
 // TODO: add damage
 #[derive(Clone, Component)]
 pub struct Enemy {
@@ -38,24 +65,44 @@ pub struct Enemy {
 #[derive(Component)]
 struct AttackCone;
 
-impl Default for Enemy {
-  fn default() -> Self {
-    let variant = match thread_rng().gen_range(0..2) {
-      0 => EnemyVariant::Aqua,
-      1 => EnemyVariant::Red,
-      2 => EnemyVariant::Green,
-      _ => EnemyVariant::Red,
-    };
+/// The collider of a delivered attack, hurting the player.
+#[derive(Component)]
+struct Strike;
+
+impl Enemy {
+  /// Every variant spawns, Green ones included.
+  fn random(rng: &mut impl Rng) -> Self {
+    let variant = EnemyVariant::ALL[rng.gen_range(0..EnemyVariant::ALL.len())];
 
     Self {
       attack_range: SPRITE_SIZE * 3.0,
       variant,
     }
   }
+
+  pub fn variant(&self) -> EnemyVariant {
+    self.variant
+  }
+}
+
+/// What killed an enemy, both are kills of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyDeathCause {
+  /// Caught in a shape drawn by the player.
+  Attack,
+  /// Knocked back into a pit.
+  Pit,
 }
 
 #[derive(Component)]
-pub struct DyingComponent;
+pub struct DyingComponent(pub EnemyDeathCause);
+
+/// Pushes the enemy away from the player, overriding its movement until the timer ends.
+#[derive(Component)]
+struct Knockback {
+  velocity: Vec2,
+  timer: Timer,
+}
 
 #[derive(Clone, Component)]
 #[component(storage = "SparseSet")]
@@ -119,6 +166,14 @@ struct Delivering {
 #[derive(Event)]
 struct DeliveringEvent(Entity, f32);
 
+/// Sent when a dying enemy is removed from the game.
+#[derive(Event)]
+pub struct EnemyKilledEvent {
+  pub variant: EnemyVariant,
+  pub position: Vec2,
+  pub cause: EnemyDeathCause,
+}
+
 impl Default for Delivering {
   fn default() -> Self {
     Self {
@@ -138,6 +193,10 @@ impl Plugin for EnemyPlugin {
         .after(init_player)
         .run_if(in_state(AppState::InGame)),
     );
+    app.add_systems(
+      Update,
+      warn_missing_spawners.run_if(in_state(AppState::InGame)),
+    );
     app.add_systems(
       Update,
       animate_sprite
@@ -172,6 +231,7 @@ impl Plugin for EnemyPlugin {
     );
 
     app.add_event::<DeliveringEvent>();
+    app.add_event::<EnemyKilledEvent>();
     app.add_systems(
       Update,
       (handle_delivering_event, tick_delivery_timer)
@@ -183,9 +243,21 @@ impl Plugin for EnemyPlugin {
       (check_for_collisions, tick_despawn_timer).run_if(in_state(AppState::InGame)),
     );
 
+    // The knockback overrides the velocity set by the states
     app.add_systems(
       Update,
-      despawn_died_enemies.run_if(in_state(AppState::InGame)),
+      (knock_back_enemies, apply_knockback)
+        .chain()
+        .run_if(in_state(AppState::InGame))
+        .after(idle)
+        .after(charge),
+    );
+    app.add_systems(
+      Update,
+      (fall_into_pits, despawn_died_enemies)
+        .chain()
+        .run_if(in_state(AppState::InGame))
+        .after(apply_knockback),
     );
   }
 }
@@ -193,6 +265,8 @@ impl Plugin for EnemyPlugin {
 fn follow(
   mut enemy_query: Query<(&Follow, &mut Velocity, &Transform), With<Enemy>>,
   player_query: Query<&Transform, With<Player>>,
+  terrain_query: Query<&Terrain>,
+  rapier_context: Res<RapierContext>,
 ) {
   for (follow, mut rb_vels, transform) in enemy_query.iter_mut() {
     let player_transform = player_query.single();
@@ -214,25 +288,60 @@ fn follow(
       Vec2::ZERO
     } else {
       let direction = direction / distance;
-      direction * follow.speed
+      // Enemies stop at the edge of pits, they only fall in when knocked back
+      let ahead = enemy_position + direction * TILE_SPRITE_SIZE;
+      match terrain_at(&rapier_context, &terrain_query, ahead) {
+        Some(Terrain::Pit) => Vec2::ZERO,
+        _ => direction * follow.speed,
+      }
     };
 
     rb_vels.linvel = velocity;
   }
 }
 
+/// Levels without an [`EnemySpawner`] still work, the enemies spawn around the player.
+fn warn_missing_spawners(
+  mut level_events: EventReader<LevelEvent>,
+  spawner_query: Query<(), With<EnemySpawner>>,
+) {
+  for event in level_events.read() {
+    if let LevelEvent::Transformed(iid) = event {
+      if spawner_query.is_empty() {
+        warn!(
+          "Level {} has no EnemySpawner, enemies spawn around the player",
+          iid
+        );
+      }
+    }
+  }
+}
+
 fn spawn_enemy(
   enemy_query: Query<&Enemy>,
-  player_query: Query<(&Transform, Entity), With<Player>>,
+  player_query: Query<(Entity, &Transform), With<Player>>,
+  spawner_query: Query<(&EnemySpawner, &GlobalTransform)>,
   texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
   ui_assets: Res<UiAssets>,
+  mut rng: ResMut<GameRng>,
+  modifiers: Res<RunModifiers>,
   mut commands: Commands,
 ) {
   if enemy_query.iter().count() >= BASE_ENEMIES as usize {
     return;
   }
 
-  let (player_initial_transform, player_entity) = player_query.get_single().unwrap();
+  let (player_entity, player_transform) = player_query.get_single().unwrap();
+  let player_position = player_transform.translation.truncate();
+
+  // Only the spawners entirely out of reach of the player are used,
+  // sorted so replays pick the same spawner
+  let mut spawners = spawner_query
+    .iter()
+    .map(|(spawner, transform)| (transform.translation().truncate(), spawner.radius))
+    .filter(|(position, radius)| position.distance(player_position) - radius >= MIN_SPAWN_DISTANCE)
+    .collect::<Vec<_>>();
+  spawners.sort_by(|(a, _), (b, _)| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
 
   let near_player = move |In(entity): In<Entity>, transforms: Query<&Transform>| {
     let enemy_transform = transforms.get(entity).unwrap();
@@ -282,17 +391,19 @@ fn spawn_enemy(
       delivered.is_ok_and(|d| d.0.timer.finished())
     };
 
+  let rng = rng.stream(RngStream::Enemy);
+
   // base circumference on BASE_ENEMIES
-  let angle = rand::thread_rng().gen_range(0.0..360.0) * PI / 180.0;
+  let angle = rng.gen_range(0.0..360.0) * PI / 180.0;
   let circumference = BASE_ENEMIES * SPRITE_SIZE;
-  let radius = (circumference / (2.0 * PI)) + rand::thread_rng().gen_range(1.5..30.0);
+  let radius = (circumference / (2.0 * PI)) + rng.gen_range(1.5..30.0);
 
   let state_machine = StateMachine::default()
     .trans::<Idle, _>(
       near_player,
       Follow {
         target: player_entity,
-        speed: rand::thread_rng().gen_range(20.0..40.0),
+        speed: rng.gen_range(20.0..40.0) * modifiers.enemy_speed,
         angle,
         player_radius: radius,
       },
@@ -311,30 +422,38 @@ fn spawn_enemy(
       entity.insert(AnimationIndices { first: 0, last: 3 });
     })
     .on_enter::<Charging>(|entity| {
-      entity.insert(AnimationIndices { first: 7, last: 9 });
+      entity.insert((AnimationIndices { first: 7, last: 9 }, Threat::Charging));
     })
     .on_enter::<Ready>(|entity| {
-      entity.insert(AnimationIndices { first: 8, last: 9 });
+      entity.insert((AnimationIndices { first: 8, last: 9 }, Threat::Ready));
     })
     .on_enter::<Delivering>(|entity| {
       entity.insert(AnimationIndices {
         first: 23,
         last: 27,
       });
+      entity.remove::<Threat>();
     });
 
   #[cfg(feature = "dev")]
   let state_machine = state_machine.set_trans_logging(true);
 
-  // we calculate the enemy position spawn based on the player position
-  // enemy will spawn at a random position around the player
-  // with a minimum radius of 100 and a maximum of 200
-  let mut rng = rand::thread_rng();
-  let distance = rng.gen_range(100.0..200.);
-  let enemy_x = player_initial_transform.translation.x + angle.cos() * distance;
-  let enemy_y = player_initial_transform.translation.y + angle.sin() * distance;
+  // enemy will spawn at a random position within a random spawner of the level,
+  // or around the player when no spawner is far enough
+  let (center, distance) = match spawners.is_empty() {
+    true => (
+      player_position,
+      rng.gen_range(MIN_SPAWN_DISTANCE..MAX_SPAWN_DISTANCE),
+    ),
+    false => {
+      let (spawner_position, spawner_radius) = spawners[rng.gen_range(0..spawners.len())];
+      (spawner_position, rng.gen_range(0.0..=spawner_radius))
+    }
+  };
+  let enemy_x = center.x + angle.cos() * distance;
+  let enemy_y = center.y + angle.sin() * distance;
 
-  let enemy = Enemy::default();
+  let enemy = Enemy::random(rng);
   let effect = make_dirt_effect(ui_assets.enemy_dirt_sprite.clone());
   let (texture, texture_atlas, timer) =
     get_idle_animation(&enemy.variant, ui_assets, texture_atlas_layouts);
@@ -420,6 +539,7 @@ fn charge(
         ..default()
       },
       AttackCone,
+      LightSource::cone(charging.range * 1.5, 1.0, colors::RED_500),
     );
 
     let attack_entity = commands.spawn(cone).id();
@@ -440,9 +560,15 @@ fn orient_towards_player(
   }
 }
 
-fn tick_charge_timer(mut query: Query<&mut Charging, With<Enemy>>, time: Res<Time>) {
+fn tick_charge_timer(
+  mut query: Query<&mut Charging, With<Enemy>>,
+  modifiers: Res<RunModifiers>,
+  time: Res<Time>,
+) {
   for mut charging_data in query.iter_mut() {
-    charging_data.timer.tick(time.delta());
+    charging_data
+      .timer
+      .tick(time.delta().div_f32(modifiers.enemy_charge_time));
   }
 }
 
@@ -470,6 +596,7 @@ fn handle_delivering_event(
   cone_query: Query<&mut Parent, With<AttackCone>>,
   assets: Res<UiAssets>,
   mut delivering_event: EventReader<DeliveringEvent>,
+  mut shake_events: EventWriter<CameraShakeEvent>,
   mut commands: Commands,
 ) {
   for evt in delivering_event.read() {
@@ -488,6 +615,7 @@ fn handle_delivering_event(
 
         commands.entity(entity).remove_children(&[*child]);
         commands.entity(*child).despawn_recursive();
+        shake_events.send(CameraShakeEvent(DELIVERY_TRAUMA));
 
         let rocks = make_attack_effect(assets.enemy_dirt_sprite.clone());
         // add collider for a frame
@@ -498,7 +626,8 @@ fn handle_delivering_event(
             ActiveEvents::COLLISION_EVENTS,
             ActiveCollisionTypes::all(),
             Sensor,
-            CollisionGroups::new(ATTACK_GROUP, PLAYER_GROUP),
+            Strike,
+            CollisionGroups::new(ATTACK_GROUP, PLAYER_GROUP | COVER_GROUP),
             DespawnTimer(Timer::from_seconds(ENEMY_DELIVER_TIME, TimerMode::Once)),
             (rocks, Playing),
           ))
@@ -531,25 +660,119 @@ fn get_ready(
 
 fn check_for_collisions(
   mut collision_events: EventReader<CollisionEvent>,
-  player_query: Query<Entity, With<Player>>,
-  mut next_state: ResMut<NextState<AppState>>,
+  player_query: Query<(Entity, &GlobalTransform), With<Player>>,
+  strike_query: Query<&GlobalTransform, With<Strike>>,
+  cover_query: Query<(), With<Cover>>,
+  rapier_context: Res<RapierContext>,
+  mut hit_events: EventWriter<PlayerHitEvent>,
+  mut cover_events: EventWriter<CoverHitEvent>,
 ) {
   for collision in collision_events.read() {
     if let CollisionEvent::Started(first_entity, entity, CollisionEventFlags::SENSOR) = collision {
-      let p = player_query.get_single().unwrap();
-      if p == *first_entity || p == *entity {
-        next_state.set(AppState::GameOver);
+      let (p, player_transform) = player_query.get_single().unwrap();
+      let (strike_transform, other) = match strike_query.get(*first_entity) {
+        Ok(transform) => (transform, *entity),
+        Err(_) => match strike_query.get(*entity) {
+          Ok(transform) => (transform, *first_entity),
+          Err(_) => continue,
+        },
+      };
+
+      if cover_query.contains(other) {
+        cover_events.send(CoverHitEvent(other));
+        continue;
+      }
+
+      // Cover tiles between the enemy and the player absorb the strike
+      let cover = cover_between(
+        &rapier_context,
+        strike_transform.translation().truncate(),
+        player_transform.translation().truncate(),
+      );
+      if other == p && cover.is_none() {
+        hit_events.send(PlayerHitEvent {
+          cause: DeathCause::EnemyAttack,
+        });
       }
     }
   }
 }
 
+/// Dodging next to the enemies knocks them back, away from the player.
+fn knock_back_enemies(
+  mut commands: Commands,
+  mut dodged_events: EventReader<PlayerDodgedEvent>,
+  player_query: Query<&GlobalTransform, With<Player>>,
+  enemy_query: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<DyingComponent>)>,
+) {
+  if dodged_events.read().count() == 0 {
+    return;
+  }
+
+  let Ok(player_transform) = player_query.get_single() else {
+    return;
+  };
+  let player_position = player_transform.translation().truncate();
+
+  for (entity, transform) in &enemy_query {
+    let offset = transform.translation().truncate() - player_position;
+    if offset.length() > KNOCKBACK_RADIUS {
+      continue;
+    }
+
+    commands.entity(entity).insert(Knockback {
+      velocity: offset.try_normalize().unwrap_or(Vec2::X) * KNOCKBACK_SPEED,
+      timer: Timer::from_seconds(KNOCKBACK_DURATION, TimerMode::Once),
+    });
+  }
+}
+
+fn apply_knockback(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut Knockback, &mut Velocity)>,
+  time: Res<Time>,
+) {
+  for (entity, mut knockback, mut velocity) in &mut query {
+    knockback.timer.tick(time.delta());
+    if knockback.timer.finished() {
+      // Charging enemies don't reset their velocity
+      velocity.linvel = Vec2::ZERO;
+      commands.entity(entity).remove::<Knockback>();
+    } else {
+      velocity.linvel = knockback.velocity;
+    }
+  }
+}
+
+/// Knocked back enemies pushed over a pit fall in and die, scoring like a single kill.
+fn fall_into_pits(
+  mut commands: Commands,
+  enemy_query: Query<(Entity, &GlobalTransform), (With<Knockback>, Without<DyingComponent>)>,
+  terrain_query: Query<&Terrain>,
+  rapier_context: Res<RapierContext>,
+  mut score_events: EventWriter<ScoreEvent>,
+) {
+  for (entity, transform) in &enemy_query {
+    let position = transform.translation().truncate();
+    if terrain_at(&rapier_context, &terrain_query, position) == Some(Terrain::Pit) {
+      commands
+        .entity(entity)
+        .insert(DyingComponent(EnemyDeathCause::Pit));
+      score_events.send(ScoreEvent {
+        source: ScoreSource::Kill { hits: 1 },
+        position,
+      });
+    }
+  }
+}
+
 fn despawn_died_enemies(
   mut commands: Commands,
-  query: Query<(Entity, &GlobalTransform, &Enemy), With<DyingComponent>>,
+  query: Query<(Entity, &GlobalTransform, &Enemy, &DyingComponent)>,
   assets: Res<UiAssets>,
+  mut killed_events: EventWriter<EnemyKilledEvent>,
 ) {
-  for (entity, transform, enemy) in query.iter() {
+  for (entity, transform, enemy, dying) in query.iter() {
     let handle = match enemy.variant {
       EnemyVariant::Aqua => assets.dead_enemy_sprite.clone(),
       EnemyVariant::Green => assets.dead_enemy_green_sprite.clone(),
@@ -558,6 +781,12 @@ fn despawn_died_enemies(
 
     let t = transform.translation();
 
+    killed_events.send(EnemyKilledEvent {
+      variant: enemy.variant,
+      position: t.truncate(),
+      cause: dying.0,
+    });
+
     let effect = commands
       .spawn_empty()
       .insert((
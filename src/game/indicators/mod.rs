@@ -0,0 +1,180 @@
+use bevy::{
+  sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+  transform::TransformSystem,
+  utils::HashSet,
+};
+
+use crate::prelude::*;
+
+use super::camera::{follow_player, GameCamera};
+
+/// Distance between the indicators and the edges of the view.
+const INDICATOR_MARGIN: f32 = 12.;
+/// Indicators shrink down to [`INDICATOR_MIN_SCALE`] as the threat gets this far from the view.
+const INDICATOR_FAR_DISTANCE: f32 = SPRITE_SIZE * 15.;
+const INDICATOR_MIN_SCALE: f32 = 0.5;
+const INDICATOR_Z_INDEX: f32 = CAMERA_Z_INDEX - 1.;
+
+/// Something dangerous, pointed at by an arrow on the edge of the screen while out of the view.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threat {
+  /// A meteor falling on its landing point.
+  Meteor,
+  /// An enemy charging its attack.
+  Charging,
+  /// An enemy about to strike.
+  Ready,
+}
+
+impl Threat {
+  const ALL: [Threat; 3] = [Threat::Meteor, Threat::Charging, Threat::Ready];
+
+  fn color(&self) -> Color {
+    match self {
+      Threat::Meteor => colors::RED_200,
+      Threat::Charging => Color::srgb(0.98, 0.75, 0.14),
+      Threat::Ready => colors::RED_500,
+    }
+  }
+}
+
+/// The arrow pointing at a [`Threat`].
+#[derive(Component)]
+struct Indicator(Entity);
+
+#[derive(Resource)]
+struct IndicatorAssets {
+  arrow: Mesh2dHandle,
+  materials: [Handle<ColorMaterial>; 3],
+}
+
+impl IndicatorAssets {
+  fn material(&self, threat: Threat) -> Handle<ColorMaterial> {
+    self.materials[threat as usize].clone()
+  }
+}
+
+impl FromWorld for IndicatorAssets {
+  fn from_world(world: &mut World) -> Self {
+    // Pointing to the right, rotated towards the threat
+    let arrow = world.resource_mut::<Assets<Mesh>>().add(Triangle2d::new(
+      Vec2::new(6., 0.),
+      Vec2::new(-4., 5.),
+      Vec2::new(-4., -5.),
+    ));
+    let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+
+    Self {
+      arrow: Mesh2dHandle(arrow),
+      materials: Threat::ALL.map(|threat| materials.add(threat.color())),
+    }
+  }
+}
+
+pub struct IndicatorsPlugin;
+
+impl Plugin for IndicatorsPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<IndicatorAssets>();
+    app.add_systems(
+      PostUpdate,
+      (sync_indicators, place_indicators)
+        .chain()
+        .after(follow_player)
+        .before(TransformSystem::TransformPropagate)
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+/// Give every threat its arrow, and remove the arrows of the threats that are gone.
+fn sync_indicators(
+  mut commands: Commands,
+  indicator_query: Query<(Entity, &Indicator)>,
+  threat_query: Query<Entity, With<Threat>>,
+  assets: Res<IndicatorAssets>,
+) {
+  let mut indicated = HashSet::new();
+  for (entity, indicator) in &indicator_query {
+    match threat_query.contains(indicator.0) {
+      true => {
+        indicated.insert(indicator.0);
+      }
+      false => commands.entity(entity).despawn_recursive(),
+    }
+  }
+
+  for threat in threat_query
+    .iter()
+    .filter(|threat| !indicated.contains(threat))
+  {
+    commands.spawn((
+      StateDespawnMarker,
+      Indicator(threat),
+      MaterialMesh2dBundle {
+        mesh: assets.arrow.clone(),
+        material: assets.material(Threat::Meteor),
+        visibility: Visibility::Hidden,
+        ..Default::default()
+      },
+    ));
+  }
+}
+
+/// Pin the arrows of the threats out of the view to its edges.
+fn place_indicators(
+  mut indicator_query: Query<(
+    &Indicator,
+    &mut Transform,
+    &mut Visibility,
+    &mut Handle<ColorMaterial>,
+  )>,
+  threat_query: Query<(&Threat, &GlobalTransform)>,
+  camera_query: Query<
+    (&Transform, &OrthographicProjection),
+    (With<GameCamera>, Without<Indicator>),
+  >,
+  assets: Res<IndicatorAssets>,
+) {
+  let Ok((camera_transform, projection)) = camera_query.get_single() else {
+    return;
+  };
+
+  let center = camera_transform.translation.truncate();
+  let view = Rect::from_center_half_size(center, projection.area.half_size());
+  let edges = view.inflate(-INDICATOR_MARGIN);
+
+  for (indicator, mut transform, mut visibility, mut material) in &mut indicator_query {
+    let Ok((threat, threat_transform)) = threat_query.get(indicator.0) else {
+      continue;
+    };
+
+    let target = threat_transform.translation().truncate();
+    let Some(direction) = (target - center).try_normalize() else {
+      *visibility = Visibility::Hidden;
+      continue;
+    };
+    if view.contains(target) {
+      *visibility = Visibility::Hidden;
+      continue;
+    }
+
+    // Where the line from the center of the view to the threat leaves the edges
+    let half_size = edges.half_size();
+    let reach = (half_size.x / direction.x.abs()).min(half_size.y / direction.y.abs());
+    let position = center + direction * reach;
+
+    let closeness = 1. - (target.distance(position) / INDICATOR_FAR_DISTANCE).min(1.);
+    let scale = INDICATOR_MIN_SCALE + (1. - INDICATOR_MIN_SCALE) * closeness;
+
+    *transform = Transform::from_translation(position.extend(INDICATOR_Z_INDEX))
+      .with_rotation(Quat::from_rotation_z(direction.to_angle()))
+      .with_scale(Vec3::splat(scale));
+    *visibility = Visibility::Inherited;
+
+    let threat_material = assets.material(*threat);
+    if *material != threat_material {
+      *material = threat_material;
+    }
+  }
+}
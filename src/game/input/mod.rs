@@ -0,0 +1,164 @@
+use bevy::{input::InputSystem, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::camera::{GameCamera, ScreenCamera};
+
+/// The player input of a single fixed tick.
+///
+/// Gameplay systems never read the keyboard or the mouse directly,
+/// they read the [`PlayerInput`] resource instead, so that a run can be
+/// recorded and replayed tick by tick (see [`crate::game::replay`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+  /// Movement axes, each one in the `-1..=1` range.
+  pub axes: (i8, i8),
+  /// Whether the dodge key was pressed during the tick.
+  pub dodge: bool,
+  /// Whether the attack button was held down during the tick.
+  pub attack: bool,
+  /// The cursor position in world coordinates.
+  pub cursor: Option<(f32, f32)>,
+}
+
+impl InputFrame {
+  pub fn movement(&self) -> Vec2 {
+    Vec2::new(self.axes.0 as f32, self.axes.1 as f32).normalize_or_zero()
+  }
+
+  pub fn cursor(&self) -> Option<Vec2> {
+    self.cursor.map(|(x, y)| Vec2::new(x, y))
+  }
+}
+
+/// The input of the current fixed tick.
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+  pub frame: InputFrame,
+  /// Dodge presses are kept until the end of the frame,
+  /// so they are not lost when more than one fixed tick runs in a frame.
+  pub dodge: bool,
+}
+
+impl PlayerInput {
+  pub fn advance(&mut self, frame: InputFrame) {
+    self.frame = frame;
+    self.dodge |= frame.dodge;
+  }
+}
+
+/// Where the [`PlayerInput`] comes from.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputSource {
+  /// The keyboard and the mouse.
+  #[default]
+  Live,
+  /// A replay, see [`crate::game::replay`].
+  Replay,
+}
+
+/// Live input collected between two fixed ticks.
+#[derive(Resource, Default)]
+pub struct PendingInput {
+  frame: InputFrame,
+  attack_pressed: bool,
+}
+
+impl PendingInput {
+  /// Take the input collected since the last tick.
+  pub fn take(&mut self) -> InputFrame {
+    let mut frame = self.frame;
+    frame.attack |= self.attack_pressed;
+
+    self.frame.dodge = false;
+    self.attack_pressed = false;
+
+    frame
+  }
+}
+
+/// [`SystemSet`] in which [`PlayerInput`] is advanced on every fixed tick.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdvanceInputSet;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<PlayerInput>();
+    app.init_resource::<PendingInput>();
+    app.init_resource::<InputSource>();
+
+    app.add_systems(OnEnter(AppState::InGame), reset_input);
+    app.add_systems(
+      PreUpdate,
+      sample_input
+        .after(InputSystem)
+        .run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(
+      FixedFirst,
+      advance_live_input
+        .in_set(AdvanceInputSet)
+        .run_if(resource_equals(InputSource::Live))
+        .run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(Last, clear_dodge.run_if(in_state(AppState::InGame)));
+  }
+}
+
+fn reset_input(mut input: ResMut<PlayerInput>, mut pending: ResMut<PendingInput>) {
+  *input = PlayerInput::default();
+  *pending = PendingInput::default();
+}
+
+fn sample_input(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  mouse_input: Res<ButtonInput<MouseButton>>,
+  q_camera: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+  q_screen: Query<&Camera, With<ScreenCamera>>,
+  q_windows: Query<&Window, With<PrimaryWindow>>,
+  mut pending: ResMut<PendingInput>,
+) {
+  let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
+  let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
+  let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
+  let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
+
+  pending.frame.axes = (-(left as i8) + right as i8, -(down as i8) + up as i8);
+  pending.frame.dodge |= keyboard_input.any_just_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+  pending.frame.attack = mouse_input.pressed(MouseButton::Left);
+  // A click shorter than a fixed tick still counts as an attack
+  pending.attack_pressed |= mouse_input.just_pressed(MouseButton::Left);
+
+  let (Ok((camera, camera_transform)), Ok(screen)) = (q_camera.get_single(), q_screen.get_single())
+  else {
+    return;
+  };
+
+  pending.frame.cursor = q_windows
+    .get_single()
+    .ok()
+    .and_then(|window| window.cursor_position())
+    // The cursor is relative to the window, the game is upscaled in the letterboxed viewport
+    .and_then(|cursor| {
+      let viewport = screen.logical_viewport_rect()?;
+      let target_size = camera.logical_viewport_size()?;
+      viewport
+        .contains(cursor)
+        .then(|| (cursor - viewport.min) / viewport.size() * target_size)
+    })
+    .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    .map(|ray| (ray.origin.x, ray.origin.y));
+}
+
+/// Advance the [`PlayerInput`] with the live input.
+fn advance_live_input(mut input: ResMut<PlayerInput>, mut pending: ResMut<PendingInput>) {
+  let frame = pending.take();
+  input.advance(frame);
+}
+
+fn clear_dodge(mut input: ResMut<PlayerInput>) {
+  input.dodge = false;
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage};
+
+use super::{input::InputSource, rng::GameRng, stats::RunStats, Score};
+
+const LEADERBOARD_KEY: &str = "leaderboard";
+/// The number of runs kept in the [`Leaderboard`].
+pub const LEADERBOARD_SIZE: usize = 10;
+/// Max length of the player name.
+pub const NAME_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+  pub name: String,
+  pub score: usize,
+  pub date: Date,
+  pub cycle: usize,
+  pub kills: usize,
+  pub seed: u64,
+}
+
+/// The best runs played on this device, sorted by score.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+  pub entries: Vec<LeaderboardEntry>,
+  /// The name used for the last run, suggested for the next one.
+  pub last_name: String,
+}
+
+impl Leaderboard {
+  /// Insert a run, returning its position if it made it into the leaderboard.
+  pub fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+    // Runs with the same score are sorted by age, the oldest first
+    let position = self
+      .entries
+      .iter()
+      .position(|other| other.score < entry.score)
+      .unwrap_or(self.entries.len());
+
+    if position >= LEADERBOARD_SIZE {
+      return None;
+    }
+
+    self.entries.insert(position, entry);
+    self.entries.truncate(LEADERBOARD_SIZE);
+
+    Some(position)
+  }
+
+  /// Rename the run at the given position, remembering the name for the next runs.
+  pub fn rename(&mut self, position: usize, name: &str) {
+    if let Some(entry) = self.entries.get_mut(position) {
+      entry.name = name.to_string();
+      self.last_name = name.to_string();
+    }
+  }
+
+  pub fn save(&self) {
+    storage::save(LEADERBOARD_KEY, self);
+  }
+}
+
+/// The position of the last run in the [`Leaderboard`], if it made it.
+#[derive(Resource, Default)]
+pub struct LeaderboardPlacement(pub Option<usize>);
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(storage::load::<Leaderboard>(LEADERBOARD_KEY).unwrap_or_default());
+    app.init_resource::<LeaderboardPlacement>();
+
+    app.add_systems(OnExit(AppState::InGame), record_run);
+  }
+}
+
+fn record_run(
+  mut leaderboard: ResMut<Leaderboard>,
+  mut placement: ResMut<LeaderboardPlacement>,
+  input_source: Res<InputSource>,
+  score: Res<Score>,
+  stats: Res<RunStats>,
+  rng: Res<GameRng>,
+) {
+  // Replays are not runs
+  if *input_source == InputSource::Replay || score.0 == 0 {
+    placement.0 = None;
+    return;
+  }
+
+  let name = match leaderboard.last_name.is_empty() {
+    true => "Player".to_string(),
+    false => leaderboard.last_name.clone(),
+  };
+
+  placement.0 = leaderboard.insert(LeaderboardEntry {
+    name,
+    score: score.0,
+    date: Date::today(),
+    cycle: stats.cycle,
+    kills: stats.kills(),
+    seed: rng.seed(),
+  });
+
+  if placement.0.is_some() {
+    leaderboard.save();
+  }
+}
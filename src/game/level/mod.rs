@@ -0,0 +1,373 @@
+use bevy_ecs_ldtk::{
+  assets::LdtkProject, LdtkWorldBundle, LevelEvent, LevelIid, LevelSelection, LevelSet,
+};
+use bevy_rapier2d::prelude::Velocity;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  assets::{DataAsset, UiAssets},
+  prelude::*,
+  storage,
+};
+
+use super::{
+  cycle::{init_cycle, CycleCompletedEvent},
+  daily,
+  input::InputSource,
+  mode::{init_run_mode, GameMode, RunModifiers},
+  tiles::PlayerStart,
+};
+
+const SELECTED_LEVEL_KEY: &str = "level";
+/// The arena changes every time this many cycles are completed.
+const LEVEL_CYCLES: usize = 5;
+/// How long the fade between two arenas lasts, expressed in seconds.
+const TRANSITION_DURATION: f32 = 1.2;
+
+/// Where the layout of an arena comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum LevelSource {
+  /// A level of an LDtk project, by asset path and level identifier.
+  Ldtk { project: String, level: String },
+  /// An arena built from the run seed, see [`super::arena`].
+  Generated,
+}
+
+/// An arena, described in `assets/data/levels.ron`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LevelInfo {
+  pub name: String,
+  pub source: LevelSource,
+  /// Multiplier of the enemies speed and of the meteors density.
+  pub difficulty: f32,
+}
+
+impl LevelInfo {
+  /// Spawn the LDtk level, replacing the world when it comes from another project,
+  /// or despawn it for generated arenas.
+  fn select(
+    &self,
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    world_query: &mut Query<(Entity, &Handle<LdtkProject>, &mut LevelSet)>,
+  ) {
+    match &self.source {
+      LevelSource::Ldtk { project, level } => {
+        let project: Handle<LdtkProject> = asset_server.load(project.clone());
+        if !world_query.iter().any(|(_, handle, _)| *handle == project) {
+          for (entity, _, _) in world_query.iter() {
+            commands.entity(entity).despawn_recursive();
+          }
+          commands.spawn((
+            StateDespawnMarker,
+            LdtkWorldBundle {
+              ldtk_handle: project,
+              ..Default::default()
+            },
+          ));
+        }
+        commands.insert_resource(LevelSelection::Identifier(level.clone()));
+      }
+      LevelSource::Generated => {
+        // Without a selection, bevy_ecs_ldtk leaves the level set alone
+        commands.remove_resource::<LevelSelection>();
+        for (_, _, mut level_set) in world_query.iter_mut() {
+          level_set.iids.clear();
+        }
+      }
+    }
+  }
+}
+
+/// Every arena, in the order of the level select screen, loaded from `assets/data/levels.ron`.
+#[derive(Resource, Asset, TypePath, Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Levels(pub Vec<LevelInfo>);
+
+impl DataAsset for Levels {
+  fn validate(&self) -> Result<(), String> {
+    match self.0.is_empty() {
+      true => Err("at least one level should be defined".to_string()),
+      false => Ok(()),
+    }
+  }
+}
+
+impl Levels {
+  /// The level at the given index, falling back to the first one.
+  pub fn get(&self, index: usize) -> &LevelInfo {
+    self.0.get(index).unwrap_or(&self.0[0])
+  }
+
+  /// The level following the current one once the given cycle is completed, if it is time to switch.
+  fn after_cycle(&self, current: usize, cycle: usize) -> Option<usize> {
+    match self.0.len() >= 2 && cycle.is_multiple_of(LEVEL_CYCLES) {
+      true => Some((current + 1) % self.0.len()),
+      false => None,
+    }
+  }
+}
+
+/// The level the next endless run starts in, chosen in the level select screen.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SelectedLevel(pub usize);
+
+impl SelectedLevel {
+  pub fn save(&self) {
+    storage::save(SELECTED_LEVEL_KEY, self);
+  }
+}
+
+/// The level being played.
+///
+/// Replays bring the level they were recorded in.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct CurrentLevel(pub usize);
+
+/// Fade to the next arena, switching level halfway through.
+#[derive(Component)]
+struct LevelTransition {
+  timer: Timer,
+  next: usize,
+  switched: bool,
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(storage::load::<SelectedLevel>(SELECTED_LEVEL_KEY).unwrap_or_default());
+    app.init_resource::<CurrentLevel>();
+    app.insert_resource(LevelSelection::index(0));
+
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      (
+        init_level.run_if(resource_equals(InputSource::Live)),
+        apply_level.after(init_run_mode).before(init_cycle),
+      )
+        .chain(),
+    );
+    app.add_systems(
+      Update,
+      (start_level_transition, run_level_transition)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(
+      Update,
+      move_player_to_start.run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+/// The daily challenge has its own level, the same for every player.
+pub fn init_level(
+  mut current_level: ResMut<CurrentLevel>,
+  selected_level: Res<SelectedLevel>,
+  levels: Res<Levels>,
+  mode: Res<GameMode>,
+) {
+  current_level.0 = match *mode {
+    GameMode::Endless => selected_level.0.min(levels.0.len() - 1),
+    GameMode::Daily(date) => daily::level(date, levels.0.len()),
+  };
+}
+
+fn apply_level(
+  mut commands: Commands,
+  mut world_query: Query<(Entity, &Handle<LdtkProject>, &mut LevelSet)>,
+  mut modifiers: ResMut<RunModifiers>,
+  current_level: Res<CurrentLevel>,
+  levels: Res<Levels>,
+  asset_server: Res<AssetServer>,
+) {
+  let level = levels.get(current_level.0);
+  level.select(&mut commands, &asset_server, &mut world_query);
+  modifiers.enemy_speed *= level.difficulty;
+  modifiers.meteor_density *= level.difficulty;
+}
+
+fn start_level_transition(
+  mut commands: Commands,
+  mut completed_events: EventReader<CycleCompletedEvent>,
+  current_level: Res<CurrentLevel>,
+  levels: Res<Levels>,
+  ui: Res<UiAssets>,
+) {
+  for event in completed_events.read() {
+    let Some(next) = levels.after_cycle(current_level.0, event.index) else {
+      continue;
+    };
+
+    commands
+      .spawn((
+        StateDespawnMarker,
+        LevelTransition {
+          timer: Timer::from_seconds(TRANSITION_DURATION, TimerMode::Once),
+          next,
+          switched: false,
+        },
+        NodeBundle {
+          style: Style {
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+          },
+          background_color: colors::PRIMARY_900.with_alpha(0.).into(),
+          z_index: ZIndex::Global(5),
+          ..Default::default()
+        },
+      ))
+      .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+          levels.get(next).name.clone(),
+          TextStyle {
+            font: ui.font_sans.clone(),
+            color: colors::PRIMARY_100.with_alpha(0.),
+            font_size: 48.,
+          },
+        ));
+      });
+  }
+}
+
+fn run_level_transition(
+  mut commands: Commands,
+  mut transition_query: Query<(
+    Entity,
+    &mut LevelTransition,
+    &mut BackgroundColor,
+    &Children,
+  )>,
+  mut text_query: Query<&mut Text>,
+  mut world_query: Query<(Entity, &Handle<LdtkProject>, &mut LevelSet)>,
+  mut current_level: ResMut<CurrentLevel>,
+  mut modifiers: ResMut<RunModifiers>,
+  levels: Res<Levels>,
+  asset_server: Res<AssetServer>,
+  time: Res<Time>,
+) {
+  for (entity, mut transition, mut background_color, children) in &mut transition_query {
+    transition.timer.tick(time.delta());
+    let progress = transition.timer.fraction();
+
+    // The screen is black halfway through, when the arena is switched
+    if progress >= 0.5 && !transition.switched {
+      transition.switched = true;
+
+      let previous = levels.get(current_level.0);
+      let next = levels.get(transition.next);
+      modifiers.enemy_speed *= next.difficulty / previous.difficulty;
+      modifiers.meteor_density *= next.difficulty / previous.difficulty;
+
+      // The player is moved to the start of the new level once it spawned
+      current_level.0 = transition.next;
+      next.select(&mut commands, &asset_server, &mut world_query);
+    }
+
+    let alpha = 1. - (progress * 2. - 1.).abs();
+    background_color.0 = colors::PRIMARY_900.with_alpha(alpha);
+    for child in children.iter() {
+      if let Ok(mut text) = text_query.get_mut(*child) {
+        text.sections[0].style.color = colors::PRIMARY_100.with_alpha(alpha);
+      }
+    }
+
+    if transition.timer.finished() {
+      commands.entity(entity).despawn_recursive();
+    }
+  }
+}
+
+/// Place the player on the [`PlayerStart`] of every level that spawns,
+/// or at the default spawn of the level when it has none.
+fn move_player_to_start(
+  mut level_events: EventReader<LevelEvent>,
+  start_query: Query<&GlobalTransform, With<PlayerStart>>,
+  level_query: Query<(&LevelIid, &GlobalTransform)>,
+  mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+) {
+  for event in level_events.read() {
+    let LevelEvent::Transformed(iid) = event else {
+      continue;
+    };
+
+    let Ok((mut transform, mut velocity)) = player_query.get_single_mut() else {
+      continue;
+    };
+
+    let position = match start_query.get_single() {
+      Ok(start) => start.translation().truncate(),
+      Err(_) => {
+        warn!("Level {} has no PlayerStart, using the default spawn", iid);
+        let origin = level_query
+          .iter()
+          .find(|(level_iid, _)| *level_iid == iid)
+          .map_or(Vec2::ZERO, |(_, level)| level.translation().truncate());
+        origin + DEFAULT_PLAYER_SPAWN
+      }
+    };
+    transform.translation = position.extend(transform.translation.z);
+    *velocity = Velocity::zero();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn levels(amount: usize) -> Levels {
+    Levels(
+      (0..amount)
+        .map(|index| LevelInfo {
+          name: format!("Level {index}"),
+          source: LevelSource::Generated,
+          difficulty: 1.,
+        })
+        .collect(),
+    )
+  }
+
+  #[test]
+  fn switches_every_few_cycles() {
+    let levels = levels(3);
+    let switches: Vec<_> = (1..=LEVEL_CYCLES * 2)
+      .filter_map(|cycle| levels.after_cycle(0, cycle).map(|next| (cycle, next)))
+      .collect();
+
+    assert_eq!(switches, vec![(LEVEL_CYCLES, 1), (LEVEL_CYCLES * 2, 1)]);
+  }
+
+  #[test]
+  fn rotation_wraps_around() {
+    let levels = levels(3);
+    assert_eq!(levels.after_cycle(1, LEVEL_CYCLES), Some(2));
+    assert_eq!(levels.after_cycle(2, LEVEL_CYCLES), Some(0));
+  }
+
+  #[test]
+  fn single_level_never_switches() {
+    assert_eq!(levels(1).after_cycle(0, LEVEL_CYCLES), None);
+  }
+
+  #[test]
+  fn unknown_level_falls_back_to_first() {
+    assert_eq!(levels(2).get(7).name, "Level 0");
+  }
+
+  #[test]
+  fn empty_levels_are_invalid() {
+    assert!(levels(0).validate().is_err());
+    assert!(levels(1).validate().is_ok());
+  }
+
+  #[test]
+  fn bundled_levels_are_valid() {
+    let levels: Levels = ron::from_str(include_str!("../../../assets/data/levels.ron")).unwrap();
+    assert!(levels.validate().is_ok());
+  }
+}
@@ -0,0 +1,24 @@
+use bevy::render::render_resource::ShaderType;
+
+use crate::prelude::*;
+
+/// The lights the closest to the camera are drawn, the others are skipped.
+/// Keep in sync with `MAX_LIGHTS` in `assets/shaders/lighting.wgsl`.
+pub const MAX_LIGHTS: usize = 32;
+
+#[derive(ShaderType, Clone, Copy, Default)]
+pub struct GpuLight {
+  pub color: Vec4,
+  pub position: Vec2,
+  pub direction: Vec2,
+  pub radius: f32,
+  /// Cosine of the half angle of the cone, -1 for lights shining all around.
+  pub spread: f32,
+}
+
+#[derive(ShaderType, Clone)]
+pub struct LightingUniform {
+  pub darkness: f32,
+  pub count: u32,
+  pub lights: [GpuLight; MAX_LIGHTS],
+}
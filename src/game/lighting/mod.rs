@@ -0,0 +1,211 @@
+use bevy::{
+  render::render_resource::{AsBindGroup, ShaderRef},
+  sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle},
+  transform::TransformSystem,
+};
+use gpu::{GpuLight, LightingUniform, MAX_LIGHTS};
+
+use crate::prelude::*;
+
+use super::camera::GameCamera;
+
+// The size checks derived by `ShaderType` are only used by the GPU buffers
+#[allow(dead_code)]
+mod gpu;
+
+/// Every [`NIGHT_CYCLE_INTERVAL`]th cycle happens at night.
+const NIGHT_CYCLE_INTERVAL: usize = 3;
+/// How dark the unlit parts of the level get at night, between 0 and 1.
+const NIGHT_DARKNESS: f32 = 0.92;
+/// Time for the night to fall or to lift, expressed in seconds.
+const DARKNESS_FADE_TIME: f32 = 1.5;
+/// Drawn below the indicators and the score popups, which stay visible at night.
+const DARKNESS_Z_INDEX: f32 = CAMERA_Z_INDEX - 3.;
+
+/// Whether the cycle with the given index happens at night.
+pub fn is_night_cycle(index: usize) -> bool {
+  index.is_multiple_of(NIGHT_CYCLE_INTERVAL)
+}
+
+/// Lights up the darkness around an entity, scaled with it.
+#[derive(Component, Clone, Copy)]
+pub struct LightSource {
+  pub radius: f32,
+  pub color: Color,
+  /// Half the angle of a cone pointing along the local Y axis, or `None` to light all around.
+  pub cone: Option<f32>,
+}
+
+impl LightSource {
+  pub fn new(radius: f32, color: Color) -> Self {
+    Self {
+      radius,
+      color,
+      cone: None,
+    }
+  }
+
+  pub fn cone(radius: f32, half_angle: f32, color: Color) -> Self {
+    Self {
+      radius,
+      color,
+      cone: Some(half_angle),
+    }
+  }
+}
+
+/// The darkness of the current cycle, fading in and out at night.
+#[derive(Resource, Default)]
+pub struct Lighting {
+  pub night: bool,
+  /// Between 0 for daylight and [`NIGHT_DARKNESS`].
+  pub darkness: f32,
+}
+
+/// Covers the view with darkness, except around the [`LightSource`]s.
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+struct LightingMaterial {
+  #[uniform(0)]
+  lighting: LightingUniform,
+}
+
+impl Material2d for LightingMaterial {
+  fn fragment_shader() -> ShaderRef {
+    "shaders/lighting.wgsl".into()
+  }
+}
+
+#[derive(Component)]
+struct Darkness;
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_plugins(Material2dPlugin::<LightingMaterial>::default());
+    app.init_resource::<Lighting>();
+
+    app.add_systems(OnEnter(AppState::InGame), reset_lighting);
+    app.add_systems(
+      Update,
+      (spawn_darkness, start_night).run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(
+      PostUpdate,
+      update_lighting
+        .after(TransformSystem::TransformPropagate)
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn reset_lighting(mut lighting: ResMut<Lighting>) {
+  *lighting = Lighting::default();
+}
+
+/// Attach the darkness to the camera, so it always covers the view.
+fn spawn_darkness(
+  mut commands: Commands,
+  camera_query: Query<Entity, Added<GameCamera>>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<LightingMaterial>>,
+) {
+  for camera in &camera_query {
+    let darkness = commands
+      .spawn((
+        Darkness,
+        MaterialMesh2dBundle {
+          mesh: meshes
+            .add(Rectangle::new(VIRTUAL_WIDTH, VIRTUAL_HEIGHT))
+            .into(),
+          material: materials.add(LightingMaterial {
+            lighting: LightingUniform {
+              darkness: 0.,
+              count: 0,
+              lights: [GpuLight::default(); MAX_LIGHTS],
+            },
+          }),
+          // Relative to the camera it is attached to
+          transform: Transform::from_xyz(0., 0., DARKNESS_Z_INDEX - CAMERA_Z_INDEX),
+          visibility: Visibility::Hidden,
+          ..Default::default()
+        },
+      ))
+      .id();
+    commands.entity(camera).add_child(darkness);
+  }
+}
+
+/// Night falls when the next cycle is a night cycle, and lifts after it.
+fn start_night(
+  mut completed_events: EventReader<CycleCompletedEvent>,
+  mut lighting: ResMut<Lighting>,
+) {
+  for event in completed_events.read() {
+    lighting.night = is_night_cycle(event.index + 1);
+  }
+}
+
+fn update_lighting(
+  mut darkness_query: Query<(&Handle<LightingMaterial>, &mut Visibility), With<Darkness>>,
+  camera_query: Query<&GlobalTransform, With<GameCamera>>,
+  light_query: Query<(&LightSource, &GlobalTransform, &InheritedVisibility)>,
+  mut materials: ResMut<Assets<LightingMaterial>>,
+  mut lighting: ResMut<Lighting>,
+  time: Res<Time>,
+) {
+  let target = match lighting.night {
+    true => NIGHT_DARKNESS,
+    false => 0.,
+  };
+  let step = NIGHT_DARKNESS * time.delta_seconds() / DARKNESS_FADE_TIME;
+  lighting.darkness += (target - lighting.darkness).clamp(-step, step);
+
+  let (Ok((material, mut visibility)), Ok(camera_transform)) =
+    (darkness_query.get_single_mut(), camera_query.get_single())
+  else {
+    return;
+  };
+
+  // Nothing to draw in daylight
+  let hidden = lighting.darkness <= 0.;
+  visibility.set_if_neq(match hidden {
+    true => Visibility::Hidden,
+    false => Visibility::Inherited,
+  });
+  if hidden {
+    return;
+  }
+
+  let Some(material) = materials.get_mut(material) else {
+    return;
+  };
+
+  let center = camera_transform.translation().truncate();
+  let mut lights: Vec<GpuLight> = light_query
+    .iter()
+    .filter(|(_, _, visibility)| visibility.get())
+    .map(|(source, transform, _)| {
+      let (scale, rotation, translation) = transform.to_scale_rotation_translation();
+      GpuLight {
+        color: source.color.to_linear().to_vec4(),
+        position: translation.truncate(),
+        direction: (rotation * Vec3::Y).truncate().normalize_or_zero(),
+        radius: source.radius * scale.x.abs().max(scale.y.abs()),
+        spread: source.cone.map_or(-1., f32::cos),
+      }
+    })
+    .collect();
+  lights.sort_by(|a, b| {
+    a.position
+      .distance_squared(center)
+      .total_cmp(&b.position.distance_squared(center))
+  });
+
+  let uniform = &mut material.lighting;
+  uniform.darkness = lighting.darkness;
+  uniform.count = lights.len().min(MAX_LIGHTS) as u32;
+  for (slot, light) in uniform.lights.iter_mut().zip(lights) {
+    *slot = light;
+  }
+}
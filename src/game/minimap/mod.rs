@@ -0,0 +1,181 @@
+use bevy::render::{
+  render_asset::RenderAssetUsages,
+  render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::prelude::*;
+
+use super::{
+  camera::CameraBounds,
+  indicators::Threat,
+  tiles::{Cover, Wall},
+};
+
+/// Size of a pixel of the minimap on the screen, a pixel is a tile of the level.
+const MINIMAP_PIXEL_SIZE: f32 = 2.;
+/// Entities are drawn as squares of this many pixels.
+const MARKER_SIZE: i32 = 2;
+
+const BACKGROUND_COLOR: [u8; 4] = [10, 12, 14, 180];
+const WALL_COLOR: [u8; 4] = [104, 108, 112, 255];
+const COVER_COLOR: [u8; 4] = [156, 163, 175, 255];
+const PLAYER_COLOR: [u8; 4] = [255, 255, 255, 255];
+const ENEMY_COLOR: [u8; 4] = [94, 234, 212, 255];
+const CHARGING_COLOR: [u8; 4] = [250, 191, 36, 255];
+const READY_COLOR: [u8; 4] = [239, 68, 68, 255];
+const METEOR_COLOR: [u8; 4] = [254, 202, 202, 255];
+
+/// The image the minimap is drawn into, redrawn every frame.
+#[derive(Component)]
+struct Minimap(Handle<Image>);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(AppState::InGame), spawn_minimap);
+    app.add_systems(PostUpdate, draw_minimap.run_if(in_state(AppState::InGame)));
+  }
+}
+
+fn spawn_minimap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+  let image = images.add(Image::new_fill(
+    Extent3d::default(),
+    TextureDimension::D2,
+    &BACKGROUND_COLOR,
+    TextureFormat::Rgba8UnormSrgb,
+    RenderAssetUsages::default(),
+  ));
+
+  commands.spawn((
+    StateDespawnMarker,
+    Minimap(image.clone()),
+    ImageBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(48.),
+        left: Val::Px(16.),
+        ..Default::default()
+      },
+      image: UiImage::new(image),
+      ..Default::default()
+    },
+  ));
+}
+
+/// The pixels of the minimap, one per tile of the level.
+struct Canvas<'a> {
+  image: &'a mut Image,
+  bounds: Rect,
+}
+
+impl Canvas<'_> {
+  fn pixel(&self, position: Vec2) -> IVec2 {
+    let size = self.image.size().as_ivec2();
+    let pixel = ((position - self.bounds.min) / TILE_SPRITE_SIZE)
+      .floor()
+      .as_ivec2();
+    // Images go down while the world goes up
+    IVec2::new(pixel.x, size.y - 1 - pixel.y)
+  }
+
+  fn paint(&mut self, pixel: IVec2, color: [u8; 4]) {
+    let size = self.image.size().as_ivec2();
+    if pixel.x < 0 || pixel.y < 0 || pixel.x >= size.x || pixel.y >= size.y {
+      return;
+    }
+
+    let index = (pixel.y * size.x + pixel.x) as usize * 4;
+    self.image.data[index..index + 4].copy_from_slice(&color);
+  }
+
+  fn marker(&mut self, position: Vec2, color: [u8; 4]) {
+    let pixel = self.pixel(position);
+    for y in 0..MARKER_SIZE {
+      for x in 0..MARKER_SIZE {
+        self.paint(
+          pixel + IVec2::new(x, y) - IVec2::splat(MARKER_SIZE / 2),
+          color,
+        );
+      }
+    }
+  }
+}
+
+fn draw_minimap(
+  mut minimap_query: Query<(&Minimap, &mut Style)>,
+  mut images: ResMut<Assets<Image>>,
+  wall_query: Query<&GlobalTransform, With<Wall>>,
+  cover_query: Query<&GlobalTransform, With<Cover>>,
+  player_query: Query<&GlobalTransform, With<Player>>,
+  enemy_query: Query<(&GlobalTransform, Option<&Threat>), With<Enemy>>,
+  meteor_query: Query<(&GlobalTransform, &Threat), Without<Enemy>>,
+  bounds: Res<CameraBounds>,
+) {
+  let Ok((minimap, mut style)) = minimap_query.get_single_mut() else {
+    return;
+  };
+  let Some(image) = images.get_mut(&minimap.0) else {
+    return;
+  };
+
+  // Hidden until the level is known
+  let display = match bounds.0.is_empty() {
+    true => Display::None,
+    false => Display::Flex,
+  };
+  if style.display != display {
+    style.display = display;
+  }
+  if bounds.0.is_empty() {
+    return;
+  }
+
+  let tiles = (bounds.0.size() / TILE_SPRITE_SIZE).ceil().as_uvec2();
+  if image.size() != tiles {
+    image.resize(Extent3d {
+      width: tiles.x,
+      height: tiles.y,
+      ..Default::default()
+    });
+    style.width = Val::Px(tiles.x as f32 * MINIMAP_PIXEL_SIZE);
+    style.height = Val::Px(tiles.y as f32 * MINIMAP_PIXEL_SIZE);
+  }
+
+  for pixel in image.data.as_chunks_mut::<4>().0 {
+    *pixel = BACKGROUND_COLOR;
+  }
+
+  let mut canvas = Canvas {
+    image,
+    bounds: bounds.0,
+  };
+
+  for transform in &wall_query {
+    let pixel = canvas.pixel(transform.translation().truncate());
+    canvas.paint(pixel, WALL_COLOR);
+  }
+  for transform in &cover_query {
+    let pixel = canvas.pixel(transform.translation().truncate());
+    canvas.paint(pixel, COVER_COLOR);
+  }
+
+  for (transform, threat) in &meteor_query {
+    if *threat == Threat::Meteor {
+      canvas.marker(transform.translation().truncate(), METEOR_COLOR);
+    }
+  }
+
+  for (transform, threat) in &enemy_query {
+    let color = match threat {
+      Some(Threat::Charging) => CHARGING_COLOR,
+      Some(Threat::Ready) => READY_COLOR,
+      _ => ENEMY_COLOR,
+    };
+    canvas.marker(transform.translation().truncate(), color);
+  }
+
+  for transform in &player_query {
+    canvas.marker(transform.translation().truncate(), PLAYER_COLOR);
+  }
+}
@@ -1,18 +1,57 @@
+pub mod achievements;
+pub mod arena;
 pub mod attack;
+pub mod camera;
 pub mod common;
 pub mod cycle;
+pub mod daily;
+pub mod draft;
 pub mod enemy;
+pub mod indicators;
+pub mod input;
+pub mod leaderboard;
+pub mod level;
+pub mod lighting;
+pub mod minimap;
+pub mod mode;
 pub mod player;
+pub mod post_process;
+pub mod power_ups;
+pub mod progression;
+pub mod replay;
+pub mod rng;
+pub mod score;
+pub mod stats;
 pub mod tiles;
+pub mod time_scale;
 
+use achievements::AchievementsPlugin;
+use arena::ArenaPlugin;
 use attack::AttackPlugin;
 use bevy::audio::{PlaybackMode, Volume};
-use bevy_ecs_ldtk::{app::LdtkIntCellAppExt, LdtkWorldBundle, LevelSelection};
+use camera::CameraPlugin;
 use common::flip;
 use cycle::CyclePlugin;
+use daily::DailyPlugin;
+use draft::DraftPlugin;
 use enemy::EnemyPlugin;
+use indicators::IndicatorsPlugin;
+use input::InputPlugin;
+use leaderboard::LeaderboardPlugin;
+use level::LevelPlugin;
+use lighting::LightingPlugin;
+use minimap::MinimapPlugin;
+use mode::ModePlugin;
 use player::PlayerPlugin;
-use tiles::WallBundle;
+use post_process::PostProcessPlugin;
+use power_ups::PowerUpPlugin;
+use progression::ProgressionPlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
+use score::ScorePlugin;
+use stats::StatsPlugin;
+use tiles::TilesPlugin;
+use time_scale::TimeScalePlugin;
 
 use crate::{assets::UiAssets, prelude::*};
 
@@ -26,29 +65,46 @@ pub struct Score(pub usize);
 #[derive(Component)]
 struct ScoreComponent;
 
-#[derive(Component)]
-struct CycleComponent;
-
 impl<S: States> Plugin for GamePlugin<S> {
   fn build(&self, app: &mut App) {
     app.add_systems(OnEnter(self.state.clone()), setup_game);
-    app.insert_resource(LevelSelection::index(0));
     app.insert_resource(Score(0));
 
-    app.add_plugins((PlayerPlugin, EnemyPlugin, CyclePlugin, AttackPlugin));
-    app.add_systems(Update, (update_score, update_state));
+    app.add_plugins((
+      RngPlugin,
+      ModePlugin,
+      LevelPlugin,
+      DailyPlugin,
+      InputPlugin,
+      LeaderboardPlugin,
+      StatsPlugin,
+      ScorePlugin,
+      AchievementsPlugin,
+      ProgressionPlugin,
+      ReplayPlugin,
+    ));
+    app.add_plugins((
+      PlayerPlugin,
+      PowerUpPlugin,
+      EnemyPlugin,
+      CyclePlugin,
+      AttackPlugin,
+      DraftPlugin,
+      TilesPlugin,
+      ArenaPlugin,
+      CameraPlugin,
+      IndicatorsPlugin,
+      MinimapPlugin,
+      LightingPlugin,
+      PostProcessPlugin,
+      TimeScalePlugin,
+    ));
+    app.add_systems(Update, update_score);
     app.add_systems(Update, flip.run_if(in_state(AppState::InGame)));
-
-    app.register_ldtk_int_cell::<WallBundle>(1);
   }
 }
 
-fn setup_game(
-  mut commands: Commands,
-  ui: Res<UiAssets>,
-  mut score: ResMut<Score>,
-  state: Res<State<CycleState>>,
-) {
+fn setup_game(mut commands: Commands, ui: Res<UiAssets>, mut score: ResMut<Score>) {
   score.0 = 0;
 
   commands.spawn((
@@ -61,10 +117,6 @@ fn setup_game(
         ..Default::default()
       },
     },
-    LdtkWorldBundle {
-      ldtk_handle: ui.planet.clone(),
-      ..Default::default()
-    },
   ));
 
   commands
@@ -94,37 +146,6 @@ fn setup_game(
         ),
       ));
     });
-
-  commands
-    .spawn((
-      StateDespawnMarker,
-      NodeBundle {
-        style: Style {
-          height: Val::Px(32.),
-          position_type: PositionType::Absolute,
-          top: Val::Px(54.),
-          right: Val::Px(16.),
-          ..Default::default()
-        },
-        ..Default::default()
-      },
-    ))
-    .with_children(|parent| {
-      parent.spawn((
-        CycleComponent,
-        TextBundle::from_section(
-          match state.get() {
-            CycleState::Standard => "Standard".to_string(),
-            CycleState::Meteors => "Meteors".to_string(),
-          },
-          TextStyle {
-            font: ui.font_mono.clone(),
-            font_size: 24.,
-            color: Color::WHITE,
-          },
-        ),
-      ));
-    });
 }
 
 fn update_score(score: Res<Score>, mut query: Query<&mut Text, With<ScoreComponent>>) {
@@ -132,12 +153,3 @@ fn update_score(score: Res<Score>, mut query: Query<&mut Text, With<ScoreCompone
     text.sections[0].value = format!("{:08}", score.0);
   }
 }
-
-fn update_state(state: Res<State<CycleState>>, mut query: Query<&mut Text, With<CycleComponent>>) {
-  for mut text in query.iter_mut() {
-    text.sections[0].value = match state.get() {
-      CycleState::Standard => "Standard".to_string(),
-      CycleState::Meteors => "Meteors".to_string(),
-    };
-  }
-}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+use super::{
+  daily,
+  rng::{init_rng, NextRunSeed},
+};
+
+/// The mode of the run, chosen in the main menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GameMode {
+  /// The default mode, every run has a random seed.
+  #[default]
+  Endless,
+  /// The daily challenge of the given date, see [`daily`].
+  Daily(Date),
+}
+
+/// Tweaks to the gameplay, applied for the whole run.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunModifiers {
+  /// Multiplier of the enemies movement speed.
+  pub enemy_speed: f32,
+  /// Multiplier of the meteors falling in every cycle.
+  pub meteor_density: f32,
+  /// Max length of the trail drawn for a single attack, expressed in pixels, unlimited if `None`.
+  pub ink_limit: Option<f32>,
+  /// Multiplier of the player movement speed.
+  pub player_speed: f32,
+  /// Multiplier of the player dodge cooldown.
+  pub dodge_cooldown: f32,
+  /// Multiplier of the minimum area of a shape.
+  pub attack_area: f32,
+  /// Multiplier of how long a shape keeps killing enemies.
+  pub trail_lifetime: f32,
+  /// Multiplier of the time enemies take to charge their attack.
+  pub enemy_charge_time: f32,
+}
+
+impl Default for RunModifiers {
+  fn default() -> Self {
+    Self {
+      enemy_speed: 1.0,
+      meteor_density: 1.0,
+      ink_limit: None,
+      player_speed: 1.0,
+      dodge_cooldown: 1.0,
+      attack_area: 1.0,
+      trail_lifetime: 1.0,
+      enemy_charge_time: 1.0,
+    }
+  }
+}
+
+pub struct ModePlugin;
+
+impl Plugin for ModePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<GameMode>();
+    app.init_resource::<RunModifiers>();
+    app.add_systems(OnEnter(AppState::InGame), init_run_mode.before(init_rng));
+  }
+}
+
+pub fn init_run_mode(
+  mut commands: Commands,
+  mode: Res<GameMode>,
+  mut next_seed: ResMut<NextRunSeed>,
+) {
+  let modifiers = match *mode {
+    GameMode::Endless => RunModifiers::default(),
+    GameMode::Daily(date) => {
+      next_seed.0 = Some(daily::seed(date));
+      daily::modifiers(date)
+    }
+  };
+
+  commands.insert_resource(modifiers);
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::{
   audio::{PlaybackMode, Volume},
   prelude::*,
@@ -11,30 +13,72 @@ use sprite::get_main_animation;
 
 use crate::{assets::UiAssets, prelude::*};
 
-use super::common::animations::AnimationIndices;
+use super::{
+  camera::CameraShakeEvent,
+  common::animations::AnimationIndices,
+  input::PlayerInput,
+  lighting::LightSource,
+  mode::RunModifiers,
+  post_process::ChromaticAberrationEvent,
+  power_ups::{PowerUpEffects, PowerUpKind, SPEED_BOOST},
+  progression::{init_upgrades, RunUpgrades, UpgradeLevels},
+  tiles::{terrain_at, Terrain},
+  time_scale::HitStopEvent,
+};
 
 mod sprite;
 
 #[derive(Component)]
 pub struct FootstepsIndices(pub Option<usize>);
 
+/// Where the player spawns in a level without a `PlayerStart`, relative to its bottom left corner.
+pub const DEFAULT_PLAYER_SPAWN: Vec2 = Vec2::new(320., 320.);
+/// How long the player can not be hit after losing a life, expressed in seconds.
+const INVULNERABILITY_DURATION: f32 = 1.5;
+/// How many times per second the player blinks while invulnerable.
+const INVULNERABILITY_BLINK_RATE: f32 = 10.;
+/// Camera shake when the player loses a life.
+const PLAYER_HIT_TRAUMA: f32 = 0.6;
+/// The game freezes for this long when the player loses a life, expressed in seconds.
+const PLAYER_HIT_STOP: f32 = 0.12;
+/// How far the light of the player reaches at night.
+const PLAYER_LIGHT_RADIUS: f32 = SPRITE_SIZE * 5.;
+
 #[derive(Component)]
 pub struct Player {
   dodge_cooldown: Timer,
+  /// The dodge cooldown before the run modifiers are applied.
+  base_dodge_cooldown: Duration,
   has_ever_dodged: bool,
   last_direction: Vec2,
   speed: f32,
+  lives: usize,
+  invulnerability: Timer,
 }
 
-impl Default for Player {
-  fn default() -> Self {
+impl Player {
+  fn new(upgrades: &UpgradeLevels) -> Self {
+    let mut invulnerability = Timer::from_seconds(INVULNERABILITY_DURATION, TimerMode::Once);
+    invulnerability.tick(invulnerability.duration());
+
+    let dodge_cooldown = Timer::from_seconds(upgrades.dodge_cooldown(), TimerMode::Once);
+
     Self {
-      dodge_cooldown: Timer::from_seconds(DODGING_COOLDOWN, TimerMode::Once),
+      base_dodge_cooldown: dodge_cooldown.duration(),
+      dodge_cooldown,
       has_ever_dodged: false,
       last_direction: Vec2::ZERO,
-      speed: PLAYER_SPEED,
+      speed: upgrades.speed(),
+      lives: upgrades.lives(),
+      invulnerability,
     }
   }
+
+  /// Make the dodge available right away.
+  pub fn refresh_dodge(&mut self) {
+    let duration = self.dodge_cooldown.duration();
+    self.dodge_cooldown.tick(duration);
+  }
 }
 
 #[derive(Clone, Component)]
@@ -61,11 +105,55 @@ impl Default for Dodge {
 #[component(storage = "SparseSet")]
 struct Move;
 
+/// Sent when the player starts a dodge.
+#[derive(Event)]
+pub struct PlayerDodgedEvent;
+
+/// What ended the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeathCause {
+  EnemyAttack,
+  Meteor,
+  Spikes,
+}
+
+impl std::fmt::Display for DeathCause {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DeathCause::EnemyAttack => write!(f, "Hit by an enemy"),
+      DeathCause::Meteor => write!(f, "Crushed by a meteor"),
+      DeathCause::Spikes => write!(f, "Impaled on spikes"),
+    }
+  }
+}
+
+/// Sent when the player is hit, costing a life.
+#[derive(Event)]
+pub struct PlayerHitEvent {
+  pub cause: DeathCause,
+}
+
+/// Sent when the player is killed, ending the run.
+#[derive(Event)]
+pub struct PlayerDiedEvent {
+  pub cause: DeathCause,
+}
+
+#[derive(Component)]
+struct LivesText;
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
   fn build(&self, app: &mut App) {
-    app.add_systems(OnEnter(AppState::InGame), init_player);
+    app.add_event::<PlayerDodgedEvent>();
+    app.add_event::<PlayerHitEvent>();
+    app.add_event::<PlayerDiedEvent>();
+
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      (init_player.after(init_upgrades), spawn_lives_text),
+    );
     app.add_systems(FixedUpdate, move_player.run_if(in_state(AppState::InGame)));
     app.add_systems(
       Update,
@@ -74,6 +162,9 @@ impl Plugin for PlayerPlugin {
         tick_decelerate_timer,
         tick_dodge_cooldown_timer,
         play_footsteps,
+        (hurt_on_spikes, handle_player_hit, handle_player_death).chain(),
+        blink_invulnerable_player,
+        update_lives_text,
       )
         .run_if(in_state(AppState::InGame))
         .after(init_player),
@@ -85,6 +176,7 @@ pub fn init_player(
   mut commands: Commands,
   texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
   ui_assets: Res<UiAssets>,
+  run_upgrades: Res<RunUpgrades>,
 ) {
   let has_moved = move |In(entity): In<Entity>, query: Query<&Player>| {
     let ctrl = query.get(entity);
@@ -97,13 +189,10 @@ pub fn init_player(
 
   let has_dodged = move |In(entity): In<Entity>,
                          query: Query<&Player, Without<Dodge>>,
-                         keyboard: Res<ButtonInput<KeyCode>>| {
+                         input: Res<PlayerInput>| {
     let player_result = query.get(entity);
     match player_result {
-      Ok(player) => {
-        (player.dodge_cooldown.finished() || !player.has_ever_dodged)
-          && keyboard.any_just_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
-      }
+      Ok(player) => (player.dodge_cooldown.finished() || !player.has_ever_dodged) && input.dodge,
       Err(_) => false,
     }
   };
@@ -153,14 +242,23 @@ pub fn init_player(
         })
         .set_trans_logging(true),
       Collider::cuboid(8., 8.),
-      CollisionGroups::new(PLAYER_GROUP, ATTACK_GROUP),
+      CollisionGroups::new(
+        PLAYER_GROUP,
+        ATTACK_GROUP | POWER_UP_GROUP | TERRAIN_GROUP | COVER_GROUP,
+      ),
       ActiveCollisionTypes::all(),
-      SpatialBundle::from_transform(Transform::from_xyz(320.0, 320.0, PLAYER_Z_INDEX)),
+      SpatialBundle::from_transform(Transform::from_translation(
+        DEFAULT_PLAYER_SPAWN.extend(PLAYER_Z_INDEX),
+      )),
       ActiveEvents::COLLISION_EVENTS,
       RigidBody::Dynamic,
       LockedAxes::ROTATION_LOCKED,
       Velocity::zero(),
-      Player::default(),
+      (
+        Player::new(&run_upgrades.0),
+        PowerUpEffects::default(),
+        LightSource::new(PLAYER_LIGHT_RADIUS, colors::PRIMARY_100),
+      ),
       GravityScale(0.),
       AnimationIndices { first: 0, last: 11 },
       AudioBundle {
@@ -187,40 +285,39 @@ pub fn init_player(
         atlas,
         animation_timer,
       ));
-      parent.spawn(Camera2dBundle {
-        transform: Transform::from_xyz(0., 0., CAMERA_Z_INDEX),
-        projection: OrthographicProjection {
-          scale: 0.4,
-          ..Default::default()
-        },
-        ..Default::default()
-      });
     });
 }
 
 fn move_player(
-  keyboard_input: Res<ButtonInput<KeyCode>>,
-  mut player_info: Query<(&mut Player, &mut Velocity), Without<Dodge>>,
+  input: Res<PlayerInput>,
+  mut player_info: Query<(&mut Player, &mut Velocity, &PowerUpEffects, &Transform), Without<Dodge>>,
+  terrain_query: Query<&Terrain>,
+  rapier_context: Res<RapierContext>,
+  modifiers: Res<RunModifiers>,
 ) {
-  for (mut player, mut rb_vels) in &mut player_info {
-    let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
-    let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
-    let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-    let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
-
-    let x_axis = -(left as i8) + right as i8;
-    let y_axis = -(down as i8) + up as i8;
-
-    let move_delta = Vec2::new(x_axis as f32, y_axis as f32).normalize_or_zero();
+  for (mut player, mut rb_vels, effects, transform) in &mut player_info {
+    let move_delta = input.frame.movement();
+    let mut speed = match effects.is_active(PowerUpKind::SpeedBoost) {
+      true => player.speed * modifiers.player_speed * SPEED_BOOST,
+      false => player.speed * modifiers.player_speed,
+    };
+    let position = transform.translation.truncate();
+    if let Some(terrain) = terrain_at(&rapier_context, &terrain_query, position) {
+      speed *= terrain.speed();
+    }
 
     // Update the velocity on the rigid_body_component,
     // the bevy_rapier plugin will update the Sprite transform.
-    rb_vels.linvel = move_delta * player.speed;
+    rb_vels.linvel = move_delta * speed;
     player.last_direction = rb_vels.linvel;
   }
 }
 
-fn dodge(mut player_query: Query<(&mut Dodge, &mut Player, &mut Velocity)>) {
+fn dodge(
+  mut player_query: Query<(&mut Dodge, &mut Player, &mut Velocity)>,
+  mut dodged_events: EventWriter<PlayerDodgedEvent>,
+  modifiers: Res<RunModifiers>,
+) {
   let player = player_query.get_single_mut();
   #[allow(clippy::single_match)]
   match player {
@@ -229,10 +326,15 @@ fn dodge(mut player_query: Query<(&mut Dodge, &mut Player, &mut Velocity)>) {
         return;
       }
 
+      let cooldown = usable_player
+        .base_dodge_cooldown
+        .mul_f32(modifiers.dodge_cooldown);
+      usable_player.dodge_cooldown.set_duration(cooldown);
       usable_player.dodge_cooldown.reset();
       usable_player.has_ever_dodged = true;
       dodge.is_dodging = true;
       velocity.linvel = usable_player.last_direction * DODGING_SPEED;
+      dodged_events.send(PlayerDodgedEvent);
     }
     Err(_) => (),
   };
@@ -247,6 +349,7 @@ fn tick_decelerate_timer(mut timer_query: Query<&mut Dodge, With<Player>>, time:
 fn tick_dodge_cooldown_timer(mut query: Query<&mut Player>, time: Res<Time>) {
   for mut player in &mut query {
     player.dodge_cooldown.tick(time.delta());
+    player.invulnerability.tick(time.delta());
   }
 }
 
@@ -277,3 +380,124 @@ fn play_footsteps(
     footsteps.0 = Some(new_index);
   }
 }
+
+/// Dodging is the only way to cross spikes unharmed.
+fn hurt_on_spikes(
+  player_query: Query<&Transform, (With<Player>, Without<Dodge>)>,
+  terrain_query: Query<&Terrain>,
+  rapier_context: Res<RapierContext>,
+  mut hit_events: EventWriter<PlayerHitEvent>,
+) {
+  let Ok(transform) = player_query.get_single() else {
+    return;
+  };
+
+  let position = transform.translation.truncate();
+  if terrain_at(&rapier_context, &terrain_query, position) == Some(Terrain::Spikes) {
+    hit_events.send(PlayerHitEvent {
+      cause: DeathCause::Spikes,
+    });
+  }
+}
+
+fn handle_player_hit(
+  mut hit_events: EventReader<PlayerHitEvent>,
+  mut died_events: EventWriter<PlayerDiedEvent>,
+  mut shake_events: EventWriter<CameraShakeEvent>,
+  mut aberration_events: EventWriter<ChromaticAberrationEvent>,
+  mut hit_stop_events: EventWriter<HitStopEvent>,
+  mut query: Query<&mut Player>,
+) {
+  let Ok(mut player) = query.get_single_mut() else {
+    return;
+  };
+
+  for event in hit_events.read() {
+    if player.lives == 0 || !player.invulnerability.finished() {
+      continue;
+    }
+
+    player.lives -= 1;
+    shake_events.send(CameraShakeEvent(PLAYER_HIT_TRAUMA));
+    aberration_events.send(ChromaticAberrationEvent(1.));
+    hit_stop_events.send(HitStopEvent {
+      duration: PLAYER_HIT_STOP,
+      speed: 0.,
+    });
+    if player.lives == 0 {
+      died_events.send(PlayerDiedEvent { cause: event.cause });
+    } else {
+      player.invulnerability.reset();
+    }
+  }
+}
+
+fn blink_invulnerable_player(
+  player_query: Query<(&Player, &Children)>,
+  mut sprite_query: Query<&mut Visibility, With<Sprite>>,
+) {
+  for (player, children) in &player_query {
+    let visible = player.invulnerability.finished()
+      || (player.invulnerability.elapsed_secs() * INVULNERABILITY_BLINK_RATE) as u32 % 2 == 0;
+
+    let mut iter = sprite_query.iter_many_mut(children);
+    while let Some(mut visibility) = iter.fetch_next() {
+      *visibility = match visible {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+      };
+    }
+  }
+}
+
+fn spawn_lives_text(mut commands: Commands, ui: Res<UiAssets>) {
+  commands.spawn((
+    StateDespawnMarker,
+    LivesText,
+    TextBundle::from_section(
+      String::new(),
+      TextStyle {
+        font: ui.font_mono.clone(),
+        font_size: 20.,
+        color: Color::WHITE,
+      },
+    )
+    .with_style(Style {
+      position_type: PositionType::Absolute,
+      bottom: Val::Px(16.),
+      right: Val::Px(16.),
+      ..Default::default()
+    }),
+  ));
+}
+
+/// Lives are only shown when the player has more than one.
+fn update_lives_text(
+  player_query: Query<&Player, Changed<Player>>,
+  mut text_query: Query<&mut Text, With<LivesText>>,
+  run_upgrades: Res<RunUpgrades>,
+) {
+  let Ok(player) = player_query.get_single() else {
+    return;
+  };
+
+  let label = match run_upgrades.0.lives() {
+    1 => String::new(),
+    _ => format!("Lives {}", player.lives),
+  };
+
+  for mut text in &mut text_query {
+    if text.sections[0].value != label {
+      text.sections[0].value = label.clone();
+    }
+  }
+}
+
+fn handle_player_death(
+  mut died_events: EventReader<PlayerDiedEvent>,
+  mut next_state: ResMut<NextState<AppState>>,
+) {
+  if died_events.read().count() > 0 {
+    next_state.set(AppState::GameOver);
+  }
+}
@@ -0,0 +1,108 @@
+use bevy::render::{extract_component::ExtractComponent, render_resource::ShaderType};
+
+use crate::prelude::*;
+
+use super::node::{PostProcessEffect, PostProcessLabel};
+
+/// Splits the color channels towards the edges of the screen.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct ChromaticAberration {
+  pub intensity: f32,
+  /// Distance between the channels in the corners at full intensity, relative to the screen.
+  pub offset: f32,
+  _padding: Vec2,
+}
+
+impl Default for ChromaticAberration {
+  fn default() -> Self {
+    Self {
+      intensity: 0.,
+      offset: 0.03,
+      _padding: Vec2::ZERO,
+    }
+  }
+}
+
+impl PostProcessEffect for ChromaticAberration {
+  const LABEL: PostProcessLabel = PostProcessLabel::ChromaticAberration;
+  const SHADER: &'static str = "shaders/post_process/chromatic_aberration.wgsl";
+
+  fn is_active(&self) -> bool {
+    self.intensity > 0.
+  }
+}
+
+/// Washes the screen with a color, its alpha is the strength of the flash.
+#[derive(Component, Clone, Copy, Default, ExtractComponent, ShaderType)]
+pub struct Flash {
+  pub color: Vec4,
+}
+
+impl PostProcessEffect for Flash {
+  const LABEL: PostProcessLabel = PostProcessLabel::Flash;
+  const SHADER: &'static str = "shaders/post_process/flash.wgsl";
+
+  fn is_active(&self) -> bool {
+    self.color.w > 0.
+  }
+}
+
+/// Darkens the corners of the screen.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct Vignette {
+  pub intensity: f32,
+  /// Distance from the center where the shade starts, 1 being the corners.
+  pub radius: f32,
+  pub softness: f32,
+  _padding: f32,
+}
+
+impl Default for Vignette {
+  fn default() -> Self {
+    Self {
+      intensity: 0.,
+      radius: 0.55,
+      softness: 0.6,
+      _padding: 0.,
+    }
+  }
+}
+
+impl PostProcessEffect for Vignette {
+  const LABEL: PostProcessLabel = PostProcessLabel::Vignette;
+  const SHADER: &'static str = "shaders/post_process/vignette.wgsl";
+
+  fn is_active(&self) -> bool {
+    self.intensity > 0.
+  }
+}
+
+/// Bends the screen and draws scanlines over it, like an old monitor.
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct Crt {
+  pub intensity: f32,
+  /// The number of scanlines on the screen, one per row of virtual pixels.
+  pub scanlines: f32,
+  pub curvature: f32,
+  _padding: f32,
+}
+
+impl Default for Crt {
+  fn default() -> Self {
+    Self {
+      intensity: 0.,
+      scanlines: VIRTUAL_HEIGHT,
+      curvature: 0.04,
+      _padding: 0.,
+    }
+  }
+}
+
+impl PostProcessEffect for Crt {
+  const LABEL: PostProcessLabel = PostProcessLabel::Crt;
+  const SHADER: &'static str = "shaders/post_process/crt.wgsl";
+
+  fn is_active(&self) -> bool {
+    self.intensity > 0.
+  }
+}
@@ -0,0 +1,258 @@
+use bevy::{
+  core_pipeline::core_2d::graph::{Core2d, Node2d},
+  render::{render_graph::RenderGraphApp, RenderApp},
+};
+use node::{PostProcessEffectPlugin, PostProcessLabel};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage};
+
+use super::camera::GameCamera;
+
+pub use effects::{ChromaticAberration, Crt, Flash, Vignette};
+
+// The size checks derived by `ShaderType` are only used by the GPU buffers
+#[allow(dead_code)]
+mod effects;
+mod node;
+
+const GRAPHICS_SETTINGS_KEY: &str = "graphics";
+
+/// Time for the chromatic aberration to fade out, expressed in seconds.
+const ABERRATION_DURATION: f32 = 0.5;
+/// Time for a full flash to fade out, expressed in seconds.
+const FLASH_DURATION: f32 = 0.3;
+/// The strongest flash only covers the screen partially.
+const FLASH_MAX_ALPHA: f32 = 0.6;
+
+/// An effect of the post-processing stack, toggled in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenEffect {
+  Crt,
+  ChromaticAberration,
+  Vignette,
+  Flash,
+}
+
+impl ScreenEffect {
+  pub const ALL: [ScreenEffect; 4] = [
+    ScreenEffect::Crt,
+    ScreenEffect::ChromaticAberration,
+    ScreenEffect::Vignette,
+    ScreenEffect::Flash,
+  ];
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      ScreenEffect::Crt => "CRT Scanlines",
+      ScreenEffect::ChromaticAberration => "Chromatic Aberration",
+      ScreenEffect::Vignette => "Vignette",
+      ScreenEffect::Flash => "Screen Flashes",
+    }
+  }
+
+  pub fn description(&self) -> &'static str {
+    match self {
+      ScreenEffect::Crt => "Curved screen and scanlines of an old monitor",
+      ScreenEffect::ChromaticAberration => "Colors split when losing a life",
+      ScreenEffect::Vignette => "Darker corners",
+      ScreenEffect::Flash => "The screen flashes on multi-kills",
+    }
+  }
+}
+
+/// The screen effects enabled by the player, saved between sessions.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsSettings {
+  pub crt: bool,
+  pub chromatic_aberration: bool,
+  pub vignette: bool,
+  pub flash: bool,
+}
+
+impl Default for GraphicsSettings {
+  fn default() -> Self {
+    Self {
+      crt: false,
+      chromatic_aberration: true,
+      vignette: true,
+      flash: true,
+    }
+  }
+}
+
+impl GraphicsSettings {
+  fn enabled_mut(&mut self, effect: ScreenEffect) -> &mut bool {
+    match effect {
+      ScreenEffect::Crt => &mut self.crt,
+      ScreenEffect::ChromaticAberration => &mut self.chromatic_aberration,
+      ScreenEffect::Vignette => &mut self.vignette,
+      ScreenEffect::Flash => &mut self.flash,
+    }
+  }
+
+  pub fn is_enabled(&self, effect: ScreenEffect) -> bool {
+    match effect {
+      ScreenEffect::Crt => self.crt,
+      ScreenEffect::ChromaticAberration => self.chromatic_aberration,
+      ScreenEffect::Vignette => self.vignette,
+      ScreenEffect::Flash => self.flash,
+    }
+  }
+
+  pub fn toggle(&mut self, effect: ScreenEffect) {
+    let enabled = self.enabled_mut(effect);
+    *enabled = !*enabled;
+  }
+
+  pub fn save(&self) {
+    storage::save(GRAPHICS_SETTINGS_KEY, self);
+  }
+}
+
+/// Split the colors of the screen, with an intensity between 0 and 1.
+#[derive(Event)]
+pub struct ChromaticAberrationEvent(pub f32);
+
+/// Flash the screen, with a strength between 0 and 1.
+#[derive(Event)]
+pub struct ScreenFlashEvent(pub f32);
+
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(
+      storage::load::<GraphicsSettings>(GRAPHICS_SETTINGS_KEY).unwrap_or_default(),
+    );
+    app.add_event::<ChromaticAberrationEvent>();
+    app.add_event::<ScreenFlashEvent>();
+
+    app.add_plugins((
+      PostProcessEffectPlugin::<ChromaticAberration>::default(),
+      PostProcessEffectPlugin::<Flash>::default(),
+      PostProcessEffectPlugin::<Vignette>::default(),
+      PostProcessEffectPlugin::<Crt>::default(),
+    ));
+
+    app.add_systems(
+      Update,
+      (
+        attach_screen_effects,
+        apply_graphics_settings,
+        fade_screen_effects,
+        trigger_screen_effects,
+      )
+        .chain(),
+    );
+
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+      return;
+    };
+
+    // The UI is drawn by the screen camera, so the HUD stays readable
+    render_app.add_render_graph_edges(
+      Core2d,
+      (
+        Node2d::Tonemapping,
+        PostProcessLabel::ChromaticAberration,
+        PostProcessLabel::Flash,
+        PostProcessLabel::Vignette,
+        PostProcessLabel::Crt,
+        Node2d::EndMainPassPostProcessing,
+      ),
+    );
+  }
+}
+
+/// The game camera gets the effects, disabled until they are needed.
+/// It renders at the virtual resolution, so the effects cover the game area and nothing else.
+fn attach_screen_effects(mut commands: Commands, camera_query: Query<Entity, Added<GameCamera>>) {
+  for camera in &camera_query {
+    commands.entity(camera).insert((
+      ChromaticAberration::default(),
+      Flash::default(),
+      Vignette::default(),
+      Crt::default(),
+    ));
+  }
+}
+
+/// Enable the permanent effects, and cut the ongoing ones when disabled.
+fn apply_graphics_settings(
+  mut camera_query: Query<(
+    &mut Crt,
+    &mut Vignette,
+    &mut ChromaticAberration,
+    &mut Flash,
+  )>,
+  settings: Res<GraphicsSettings>,
+) {
+  let intensity = |enabled: bool| match enabled {
+    true => 1.,
+    false => 0.,
+  };
+
+  for (mut crt, mut vignette, mut aberration, mut flash) in &mut camera_query {
+    if crt.intensity != intensity(settings.crt) {
+      crt.intensity = intensity(settings.crt);
+    }
+    if vignette.intensity != intensity(settings.vignette) {
+      vignette.intensity = intensity(settings.vignette);
+    }
+    if !settings.chromatic_aberration && aberration.intensity > 0. {
+      aberration.intensity = 0.;
+    }
+    if !settings.flash && flash.color.w > 0. {
+      flash.color.w = 0.;
+    }
+  }
+}
+
+/// The effects fade in real time, so they still play out while the game is paused or slowed down.
+fn fade_screen_effects(
+  mut camera_query: Query<(&mut ChromaticAberration, &mut Flash)>,
+  time: Res<Time<Real>>,
+) {
+  for (mut aberration, mut flash) in &mut camera_query {
+    if aberration.intensity > 0. {
+      aberration.intensity =
+        (aberration.intensity - time.delta_seconds() / ABERRATION_DURATION).max(0.);
+    }
+    if flash.color.w > 0. {
+      flash.color.w =
+        (flash.color.w - FLASH_MAX_ALPHA * time.delta_seconds() / FLASH_DURATION).max(0.);
+    }
+  }
+}
+
+fn trigger_screen_effects(
+  mut aberration_events: EventReader<ChromaticAberrationEvent>,
+  mut flash_events: EventReader<ScreenFlashEvent>,
+  mut camera_query: Query<(&mut ChromaticAberration, &mut Flash)>,
+  settings: Res<GraphicsSettings>,
+) {
+  let aberration = aberration_events
+    .read()
+    .map(|ChromaticAberrationEvent(intensity)| *intensity)
+    .fold(0., f32::max)
+    .min(1.);
+  let flash = flash_events
+    .read()
+    .map(|ScreenFlashEvent(strength)| *strength)
+    .fold(0., f32::max)
+    .min(1.);
+
+  for (mut camera_aberration, mut camera_flash) in &mut camera_query {
+    if settings.chromatic_aberration && aberration > camera_aberration.intensity {
+      camera_aberration.intensity = aberration;
+    }
+    if settings.flash && flash * FLASH_MAX_ALPHA > camera_flash.color.w {
+      camera_flash.color = colors::PRIMARY_100
+        .to_linear()
+        .with_alpha(flash * FLASH_MAX_ALPHA)
+        .to_vec4();
+    }
+  }
+}
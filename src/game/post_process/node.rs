@@ -0,0 +1,215 @@
+use std::marker::PhantomData;
+
+use bevy::{
+  core_pipeline::{
+    core_2d::graph::Core2d, fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+  },
+  ecs::query::QueryItem,
+  render::{
+    extract_component::{
+      ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+      UniformComponentPlugin,
+    },
+    render_graph::{
+      NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+    },
+    render_resource::{
+      binding_types::{sampler, texture_2d, uniform_buffer},
+      encase::internal::WriteInto,
+      BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries, CachedRenderPipelineId,
+      ColorTargetState, ColorWrites, FragmentState, MultisampleState, Operations, PipelineCache,
+      PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+      Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureFormat,
+      TextureSampleType,
+    },
+    renderer::{RenderContext, RenderDevice},
+    texture::BevyDefault,
+    view::ViewTarget,
+    RenderApp,
+  },
+};
+
+use crate::prelude::*;
+
+/// The nodes of the post-processing stack in the 2D render graph.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+pub enum PostProcessLabel {
+  ChromaticAberration,
+  Flash,
+  Vignette,
+  Crt,
+}
+
+/// A fullscreen pass applied to the cameras having the effect, with the effect as its uniform.
+pub trait PostProcessEffect: Component + ExtractComponent + ShaderType + WriteInto + Clone {
+  const LABEL: PostProcessLabel;
+  /// Path of the shader in the assets, with a `fragment` entry point.
+  const SHADER: &'static str;
+
+  /// Inactive effects skip their pass.
+  fn is_active(&self) -> bool;
+}
+
+/// Adds the node of an effect to the 2D render graph, its edges are up to the caller.
+pub struct PostProcessEffectPlugin<E>(PhantomData<E>);
+
+impl<E> Default for PostProcessEffectPlugin<E> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<E: PostProcessEffect> Plugin for PostProcessEffectPlugin<E> {
+  fn build(&self, app: &mut App) {
+    app.add_plugins((
+      ExtractComponentPlugin::<E>::default(),
+      UniformComponentPlugin::<E>::default(),
+    ));
+
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+      return;
+    };
+
+    render_app.add_render_graph_node::<ViewNodeRunner<PostProcessNode<E>>>(Core2d, E::LABEL);
+  }
+
+  fn finish(&self, app: &mut App) {
+    let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+      return;
+    };
+
+    render_app.init_resource::<PostProcessPipeline<E>>();
+  }
+}
+
+struct PostProcessNode<E>(PhantomData<E>);
+
+impl<E> Default for PostProcessNode<E> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<E: PostProcessEffect> ViewNode for PostProcessNode<E> {
+  type ViewQuery = (
+    &'static ViewTarget,
+    &'static E,
+    &'static DynamicUniformIndex<E>,
+  );
+
+  fn run(
+    &self,
+    _graph: &mut RenderGraphContext,
+    render_context: &mut RenderContext,
+    (view_target, effect, uniform_index): QueryItem<Self::ViewQuery>,
+    world: &World,
+  ) -> Result<(), NodeRunError> {
+    if !effect.is_active() {
+      return Ok(());
+    }
+
+    let post_process_pipeline = world.resource::<PostProcessPipeline<E>>();
+    let pipeline_cache = world.resource::<PipelineCache>();
+
+    // The shader may still be loading
+    let Some(pipeline) = pipeline_cache.get_render_pipeline(post_process_pipeline.pipeline_id)
+    else {
+      return Ok(());
+    };
+
+    let uniforms = world.resource::<ComponentUniforms<E>>();
+    let Some(uniforms_binding) = uniforms.uniforms().binding() else {
+      return Ok(());
+    };
+
+    // Reads the output of the previous pass and writes to the other texture of the view
+    let post_process = view_target.post_process_write();
+
+    let bind_group = render_context.render_device().create_bind_group(
+      "post_process_bind_group",
+      &post_process_pipeline.layout,
+      &BindGroupEntries::sequential((
+        post_process.source,
+        &post_process_pipeline.sampler,
+        uniforms_binding,
+      )),
+    );
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+      label: Some("post_process_pass"),
+      color_attachments: &[Some(RenderPassColorAttachment {
+        view: post_process.destination,
+        resolve_target: None,
+        ops: Operations::default(),
+      })],
+      depth_stencil_attachment: None,
+      timestamp_writes: None,
+      occlusion_query_set: None,
+    });
+
+    render_pass.set_render_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+    render_pass.draw(0..3, 0..1);
+
+    Ok(())
+  }
+}
+
+#[derive(Resource)]
+struct PostProcessPipeline<E> {
+  layout: BindGroupLayout,
+  sampler: Sampler,
+  pipeline_id: CachedRenderPipelineId,
+  effect: PhantomData<E>,
+}
+
+impl<E: PostProcessEffect> FromWorld for PostProcessPipeline<E> {
+  fn from_world(world: &mut World) -> Self {
+    let render_device = world.resource::<RenderDevice>();
+
+    let layout = render_device.create_bind_group_layout(
+      "post_process_bind_group_layout",
+      &BindGroupLayoutEntries::sequential(
+        ShaderStages::FRAGMENT,
+        (
+          texture_2d(TextureSampleType::Float { filterable: true }),
+          sampler(SamplerBindingType::Filtering),
+          uniform_buffer::<E>(true),
+        ),
+      ),
+    );
+
+    let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+    let shader = world.load_asset(E::SHADER);
+
+    let pipeline_id =
+      world
+        .resource_mut::<PipelineCache>()
+        .queue_render_pipeline(RenderPipelineDescriptor {
+          label: Some("post_process_pipeline".into()),
+          layout: vec![layout.clone()],
+          vertex: fullscreen_shader_vertex_state(),
+          fragment: Some(FragmentState {
+            shader,
+            shader_defs: vec![],
+            entry_point: "fragment".into(),
+            targets: vec![Some(ColorTargetState {
+              format: TextureFormat::bevy_default(),
+              blend: None,
+              write_mask: ColorWrites::ALL,
+            })],
+          }),
+          primitive: PrimitiveState::default(),
+          depth_stencil: None,
+          multisample: MultisampleState::default(),
+          push_constant_ranges: vec![],
+        });
+
+    Self {
+      layout,
+      sampler,
+      pipeline_id,
+      effect: PhantomData,
+    }
+  }
+}
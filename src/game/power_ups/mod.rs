@@ -0,0 +1,360 @@
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{assets::UiAssets, prelude::*};
+
+use super::{
+  cycle::CycleCompletedEvent,
+  rng::{GameRng, RngStream},
+};
+
+/// Chance for a killed enemy to drop a power-up.
+const ENEMY_DROP_CHANCE: f64 = 0.1;
+/// How long a power-up stays on the ground, expressed in seconds.
+const PICKUP_LIFETIME: f32 = 10.;
+/// Portion of [`PICKUP_LIFETIME`] left when the pickup starts blinking.
+const PICKUP_BLINK: f32 = 0.3;
+const PICKUP_SIZE: f32 = 10.;
+/// Multiplier of the player speed while [`PowerUpKind::SpeedBoost`] is active.
+pub const SPEED_BOOST: f32 = 1.5;
+/// Radius of the trail collider while [`PowerUpKind::WideTrail`] is active.
+pub const WIDE_TRAIL_RADIUS: f32 = SPRITE_SIZE / 2.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+  SpeedBoost,
+  DoubleScore,
+  DodgeRefresh,
+  WideTrail,
+  MeteorShield,
+}
+
+impl PowerUpKind {
+  pub const ALL: [PowerUpKind; 5] = [
+    PowerUpKind::SpeedBoost,
+    PowerUpKind::DoubleScore,
+    PowerUpKind::DodgeRefresh,
+    PowerUpKind::WideTrail,
+    PowerUpKind::MeteorShield,
+  ];
+
+  /// How long the effect lasts, expressed in seconds. `None` for instant effects.
+  pub fn duration(&self) -> Option<f32> {
+    match self {
+      PowerUpKind::SpeedBoost => Some(6.),
+      PowerUpKind::DoubleScore => Some(8.),
+      PowerUpKind::DodgeRefresh => None,
+      PowerUpKind::WideTrail => Some(8.),
+      PowerUpKind::MeteorShield => Some(15.),
+    }
+  }
+
+  /// Short label shown on the pickup icon.
+  pub fn label(&self) -> &'static str {
+    match self {
+      PowerUpKind::SpeedBoost => "SPD",
+      PowerUpKind::DoubleScore => "x2",
+      PowerUpKind::DodgeRefresh => "DDG",
+      PowerUpKind::WideTrail => "INK",
+      PowerUpKind::MeteorShield => "SHD",
+    }
+  }
+
+  pub fn color(&self) -> Color {
+    match self {
+      PowerUpKind::SpeedBoost => Color::srgb(0.98, 0.8, 0.08),
+      PowerUpKind::DoubleScore => Color::srgb(0.29, 0.87, 0.5),
+      PowerUpKind::DodgeRefresh => Color::srgb(0.38, 0.65, 0.98),
+      PowerUpKind::WideTrail => colors::PRIMARY_100,
+      PowerUpKind::MeteorShield => colors::RED_400,
+    }
+  }
+}
+
+/// A power-up lying on the ground, waiting to be collected.
+#[derive(Component)]
+pub struct PowerUp {
+  kind: PowerUpKind,
+  lifetime: Timer,
+}
+
+/// The timed effects of the collected power-ups.
+#[derive(Component, Default)]
+pub struct PowerUpEffects(Vec<(PowerUpKind, Timer)>);
+
+impl PowerUpEffects {
+  pub fn is_active(&self, kind: PowerUpKind) -> bool {
+    self.0.iter().any(|(active, _)| *active == kind)
+  }
+
+  /// Remaining time of the effect, expressed in seconds.
+  pub fn remaining(&self, kind: PowerUpKind) -> Option<f32> {
+    self
+      .0
+      .iter()
+      .find(|(active, _)| *active == kind)
+      .map(|(_, timer)| timer.remaining_secs())
+  }
+
+  /// Collecting an active power-up again restarts its timer.
+  fn activate(&mut self, kind: PowerUpKind) {
+    let Some(duration) = kind.duration() else {
+      return;
+    };
+
+    self.0.retain(|(active, _)| *active != kind);
+    self
+      .0
+      .push((kind, Timer::from_seconds(duration, TimerMode::Once)));
+  }
+
+  /// End the effect early, e.g. when the shield absorbs a meteor.
+  pub fn consume(&mut self, kind: PowerUpKind) {
+    self.0.retain(|(active, _)| *active != kind);
+  }
+}
+
+#[derive(Component)]
+struct PowerUpIcon(PowerUpKind);
+
+#[derive(Component)]
+struct PowerUpTimerText(PowerUpKind);
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(AppState::InGame), spawn_power_up_hud);
+    app.add_systems(
+      Update,
+      (
+        (drop_from_enemies, drop_from_cycles),
+        collect_power_ups,
+        tick_power_up_effects,
+        despawn_expired_pickups,
+        update_power_up_hud,
+      )
+        .chain()
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn random_kind(rng: &mut impl Rng) -> PowerUpKind {
+  PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())]
+}
+
+fn spawn_pickup(commands: &mut Commands, kind: PowerUpKind, position: Vec2) {
+  commands.spawn((
+    StateDespawnMarker,
+    PowerUp {
+      kind,
+      lifetime: Timer::from_seconds(PICKUP_LIFETIME, TimerMode::Once),
+    },
+    SpriteBundle {
+      sprite: Sprite {
+        color: kind.color(),
+        custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+        ..Default::default()
+      },
+      transform: Transform::from_translation(position.extend(PLAYER_Z_INDEX - 1.))
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+      ..Default::default()
+    },
+    Collider::cuboid(PICKUP_SIZE / 2., PICKUP_SIZE / 2.),
+    CollisionGroups::new(POWER_UP_GROUP, PLAYER_GROUP),
+    ActiveCollisionTypes::all(),
+    ActiveEvents::COLLISION_EVENTS,
+    Sensor,
+  ));
+}
+
+fn drop_from_enemies(
+  mut commands: Commands,
+  mut killed_events: EventReader<EnemyKilledEvent>,
+  mut rng: ResMut<GameRng>,
+) {
+  let rng = rng.stream(RngStream::PowerUp);
+
+  for event in killed_events.read() {
+    if rng.gen_bool(ENEMY_DROP_CHANCE) {
+      spawn_pickup(&mut commands, random_kind(rng), event.position);
+    }
+  }
+}
+
+/// Every completed cycle drops a power-up next to the player.
+fn drop_from_cycles(
+  mut commands: Commands,
+  mut completed_events: EventReader<CycleCompletedEvent>,
+  player_query: Query<&Transform, With<Player>>,
+  mut rng: ResMut<GameRng>,
+) {
+  let rng = rng.stream(RngStream::PowerUp);
+  let Ok(player_transform) = player_query.get_single() else {
+    return;
+  };
+
+  for _ in completed_events.read() {
+    let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+    let offset = Vec2::from_angle(angle) * SPRITE_SIZE * 3.;
+    spawn_pickup(
+      &mut commands,
+      random_kind(rng),
+      player_transform.translation.truncate() + offset,
+    );
+  }
+}
+
+fn collect_power_ups(
+  mut commands: Commands,
+  mut collision_events: EventReader<CollisionEvent>,
+  mut player_query: Query<(Entity, &mut Player, &mut PowerUpEffects)>,
+  pickup_query: Query<&PowerUp>,
+) {
+  let Ok((player_entity, mut player, mut effects)) = player_query.get_single_mut() else {
+    return;
+  };
+
+  for collision in collision_events.read() {
+    let CollisionEvent::Started(first_entity, second_entity, _) = collision else {
+      continue;
+    };
+
+    let pickup_entity = match (*first_entity, *second_entity) {
+      (entity, other) | (other, entity) if other == player_entity => entity,
+      _ => continue,
+    };
+
+    let Ok(pickup) = pickup_query.get(pickup_entity) else {
+      continue;
+    };
+
+    match pickup.kind {
+      PowerUpKind::DodgeRefresh => player.refresh_dodge(),
+      kind => effects.activate(kind),
+    }
+
+    commands.entity(pickup_entity).despawn_recursive();
+  }
+}
+
+fn tick_power_up_effects(mut query: Query<&mut PowerUpEffects>, time: Res<Time>) {
+  for mut effects in &mut query {
+    for (_, timer) in effects.0.iter_mut() {
+      timer.tick(time.delta());
+    }
+    effects.0.retain(|(_, timer)| !timer.finished());
+  }
+}
+
+fn despawn_expired_pickups(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut PowerUp, &mut Visibility)>,
+  time: Res<Time>,
+) {
+  for (entity, mut pickup, mut visibility) in &mut query {
+    pickup.lifetime.tick(time.delta());
+    if pickup.lifetime.finished() {
+      commands.entity(entity).despawn_recursive();
+      continue;
+    }
+
+    // Blink before disappearing
+    let remaining = pickup.lifetime.remaining_secs();
+    let blinking = remaining < PICKUP_LIFETIME * PICKUP_BLINK && (remaining * 8.) as u32 % 2 == 0;
+    *visibility = match blinking {
+      true => Visibility::Hidden,
+      false => Visibility::Inherited,
+    };
+  }
+}
+
+fn spawn_power_up_hud(mut commands: Commands, ui: Res<UiAssets>) {
+  commands
+    .spawn((
+      StateDespawnMarker,
+      NodeBundle {
+        style: Style {
+          position_type: PositionType::Absolute,
+          bottom: Val::Px(16.),
+          left: Val::Px(16.),
+          column_gap: Val::Px(8.),
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+    ))
+    .with_children(|parent| {
+      for kind in PowerUpKind::ALL {
+        if kind.duration().is_none() {
+          continue;
+        }
+
+        parent
+          .spawn((
+            PowerUpIcon(kind),
+            NodeBundle {
+              style: Style {
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(4.)),
+                border: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+              },
+              border_color: kind.color().into(),
+              background_color: colors::PRIMARY_700.into(),
+              ..Default::default()
+            },
+          ))
+          .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+              kind.label(),
+              TextStyle {
+                font: ui.font_mono.clone(),
+                font_size: 16.,
+                color: kind.color(),
+              },
+            ));
+            parent.spawn((
+              PowerUpTimerText(kind),
+              TextBundle::from_section(
+                String::new(),
+                TextStyle {
+                  font: ui.font_mono.clone(),
+                  font_size: 14.,
+                  color: colors::PRIMARY_100,
+                },
+              ),
+            ));
+          });
+      }
+    });
+}
+
+fn update_power_up_hud(
+  effects_query: Query<&PowerUpEffects>,
+  mut icon_query: Query<(&PowerUpIcon, &mut Style)>,
+  mut text_query: Query<(&PowerUpTimerText, &mut Text)>,
+) {
+  let Ok(effects) = effects_query.get_single() else {
+    return;
+  };
+
+  for (icon, mut style) in &mut icon_query {
+    let display = match effects.is_active(icon.0) {
+      true => Display::Flex,
+      false => Display::None,
+    };
+    if style.display != display {
+      style.display = display;
+    }
+  }
+
+  for (timer_text, mut text) in &mut text_query {
+    if let Some(remaining) = effects.remaining(timer_text.0) {
+      text.sections[0].value = format!("{:.1}s", remaining);
+    }
+  }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage};
+
+use super::{
+  input::InputSource,
+  mode::{init_run_mode, GameMode, RunModifiers},
+  stats::RunStats,
+  Score,
+};
+
+const PROGRESSION_KEY: &str = "progression";
+
+/// Permanent improvements of the player, bought with stardust between runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+  DodgeCooldown,
+  Speed,
+  InkCapacity,
+  ExtraLife,
+}
+
+impl Upgrade {
+  pub const ALL: [Upgrade; 4] = [
+    Upgrade::DodgeCooldown,
+    Upgrade::Speed,
+    Upgrade::InkCapacity,
+    Upgrade::ExtraLife,
+  ];
+
+  pub fn title(&self) -> &'static str {
+    match self {
+      Upgrade::DodgeCooldown => "Quick Feet",
+      Upgrade::Speed => "Sprinter",
+      Upgrade::InkCapacity => "Deep Inkwell",
+      Upgrade::ExtraLife => "Second Wind",
+    }
+  }
+
+  pub fn description(&self) -> &'static str {
+    match self {
+      Upgrade::DodgeCooldown => "Dodge cooldown -0.15s",
+      Upgrade::Speed => "Movement speed +8%",
+      Upgrade::InkCapacity => "Shapes linger +20%, ink limit +20%",
+      Upgrade::ExtraLife => "One more hit before the run ends",
+    }
+  }
+
+  pub fn max_level(&self) -> u8 {
+    match self {
+      Upgrade::DodgeCooldown => 4,
+      Upgrade::Speed => 5,
+      Upgrade::InkCapacity => 5,
+      Upgrade::ExtraLife => 2,
+    }
+  }
+
+  /// The price of the next level, `None` when the upgrade is maxed.
+  pub fn cost(&self, level: u8) -> Option<usize> {
+    if level >= self.max_level() {
+      return None;
+    }
+
+    let base = match self {
+      Upgrade::DodgeCooldown => 40,
+      Upgrade::Speed => 30,
+      Upgrade::InkCapacity => 25,
+      Upgrade::ExtraLife => 200,
+    };
+
+    Some(base * (level as usize + 1))
+  }
+}
+
+/// The level of every [`Upgrade`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UpgradeLevels {
+  pub dodge_cooldown: u8,
+  pub speed: u8,
+  pub ink_capacity: u8,
+  pub extra_life: u8,
+}
+
+impl UpgradeLevels {
+  pub fn level(&self, upgrade: Upgrade) -> u8 {
+    match upgrade {
+      Upgrade::DodgeCooldown => self.dodge_cooldown,
+      Upgrade::Speed => self.speed,
+      Upgrade::InkCapacity => self.ink_capacity,
+      Upgrade::ExtraLife => self.extra_life,
+    }
+  }
+
+  fn level_mut(&mut self, upgrade: Upgrade) -> &mut u8 {
+    match upgrade {
+      Upgrade::DodgeCooldown => &mut self.dodge_cooldown,
+      Upgrade::Speed => &mut self.speed,
+      Upgrade::InkCapacity => &mut self.ink_capacity,
+      Upgrade::ExtraLife => &mut self.extra_life,
+    }
+  }
+
+  /// The dodge cooldown, expressed in seconds.
+  pub fn dodge_cooldown(&self) -> f32 {
+    DODGING_COOLDOWN - 0.15 * self.dodge_cooldown as f32
+  }
+
+  pub fn speed(&self) -> f32 {
+    PLAYER_SPEED * (1. + 0.08 * self.speed as f32)
+  }
+
+  pub fn ink_multiplier(&self) -> f32 {
+    1. + 0.2 * self.ink_capacity as f32
+  }
+
+  pub fn lives(&self) -> usize {
+    1 + self.extra_life as usize
+  }
+}
+
+/// The stardust earned and the upgrades bought on this device.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Progression {
+  pub stardust: usize,
+  pub levels: UpgradeLevels,
+}
+
+impl Progression {
+  /// Buy the next level of the upgrade, returning `false` if it can not be afforded.
+  pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+    let level = self.levels.level(upgrade);
+    let Some(cost) = upgrade.cost(level).filter(|cost| *cost <= self.stardust) else {
+      return false;
+    };
+
+    self.stardust -= cost;
+    *self.levels.level_mut(upgrade) += 1;
+    true
+  }
+
+  pub fn save(&self) {
+    storage::save(PROGRESSION_KEY, self);
+  }
+}
+
+/// The upgrades applied to the current run.
+///
+/// Replays bring the upgrades they were recorded with.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct RunUpgrades(pub UpgradeLevels);
+
+/// The stardust earned by the last run.
+#[derive(Resource, Default)]
+pub struct LastEarnings(pub usize);
+
+/// The stardust earned by a run.
+pub fn earnings(score: usize, stats: &RunStats) -> usize {
+  score / 100 + stats.kills() + stats.cycle * 5
+}
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+  fn build(&self, app: &mut App) {
+    app.insert_resource(storage::load::<Progression>(PROGRESSION_KEY).unwrap_or_default());
+    app.init_resource::<RunUpgrades>();
+    app.init_resource::<LastEarnings>();
+
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      (
+        init_upgrades.run_if(resource_equals(InputSource::Live)),
+        apply_ink_upgrade.after(init_run_mode),
+      )
+        .chain(),
+    );
+    app.add_systems(OnExit(AppState::InGame), award_stardust);
+  }
+}
+
+/// The daily challenge is the same for everyone, so it ignores the upgrades.
+pub fn init_upgrades(
+  mut run_upgrades: ResMut<RunUpgrades>,
+  progression: Res<Progression>,
+  mode: Res<GameMode>,
+) {
+  run_upgrades.0 = match *mode {
+    GameMode::Endless => progression.levels,
+    GameMode::Daily(_) => UpgradeLevels::default(),
+  };
+}
+
+fn apply_ink_upgrade(mut modifiers: ResMut<RunModifiers>, run_upgrades: Res<RunUpgrades>) {
+  let multiplier = run_upgrades.0.ink_multiplier();
+  modifiers.trail_lifetime *= multiplier;
+  if let Some(ink_limit) = modifiers.ink_limit.as_mut() {
+    *ink_limit *= multiplier;
+  }
+}
+
+fn award_stardust(
+  mut progression: ResMut<Progression>,
+  mut last_earnings: ResMut<LastEarnings>,
+  input_source: Res<InputSource>,
+  score: Res<Score>,
+  stats: Res<RunStats>,
+) {
+  // Replays are not runs
+  if *input_source == InputSource::Replay {
+    last_earnings.0 = 0;
+    return;
+  }
+
+  last_earnings.0 = earnings(score.0, &stats);
+  progression.stardust += last_earnings.0;
+  progression.save();
+}
@@ -0,0 +1,361 @@
+use std::time::Duration;
+
+use bevy::time::{TimeSystem, TimeUpdateStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::{app_state::InGameSubState, assets::UiAssets, prelude::*};
+
+use super::{
+  draft::{apply_draft_choice, DraftChosenEvent},
+  input::{AdvanceInputSet, InputFrame, InputSource, PlayerInput},
+  level::{init_level, CurrentLevel},
+  mode::GameMode,
+  progression::{init_upgrades, RunUpgrades, UpgradeLevels},
+  rng::{init_rng, GameRng, NextRunSeed},
+};
+
+/// A recorded run.
+///
+/// Replaying a run needs the same seed, the same input on every fixed tick
+/// and the same frame timing, since part of the gameplay runs in [`Update`].
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+  pub seed: u64,
+  pub mode: GameMode,
+  #[serde(default)]
+  pub upgrades: UpgradeLevels,
+  #[serde(default)]
+  pub level: usize,
+  /// The real time elapsed in every frame, expressed in nanoseconds.
+  pub frames: Vec<u32>,
+  /// The input of every fixed tick, run-length encoded.
+  pub ticks: Vec<(u32, InputFrame)>,
+  /// The card picked in every draft, with the frame it was picked in.
+  #[serde(default)]
+  pub drafts: Vec<(u32, usize)>,
+}
+
+impl Replay {
+  fn push_tick(&mut self, frame: InputFrame) {
+    match self.ticks.last_mut() {
+      Some((count, last)) if *last == frame => *count += 1,
+      _ => self.ticks.push((1, frame)),
+    }
+  }
+
+  fn push_frame(&mut self, delta: Duration) {
+    self
+      .frames
+      .push(delta.as_nanos().min(u32::MAX as u128) as u32);
+  }
+}
+
+/// The replay of the last recorded run.
+#[derive(Resource)]
+pub struct LastReplay(pub Replay);
+
+/// The run being recorded.
+#[derive(Resource)]
+struct Recorder(Replay);
+
+/// The replay being played.
+#[derive(Resource)]
+struct ReplayPlayer {
+  replay: Replay,
+  frame: usize,
+  tick: usize,
+  repeat: u32,
+  draft: usize,
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      OnEnter(AppState::InGame),
+      (
+        discard_fixed_overstep,
+        start_recording
+          .after(init_rng)
+          .after(init_upgrades)
+          .after(init_level)
+          .run_if(resource_equals(InputSource::Live)),
+        spawn_replay_label.run_if(resource_equals(InputSource::Replay)),
+      ),
+    );
+    app.add_systems(
+      OnExit(AppState::InGame),
+      (
+        stop_recording.run_if(resource_exists::<Recorder>),
+        stop_replay.run_if(resource_exists::<ReplayPlayer>),
+      ),
+    );
+
+    app.add_systems(
+      First,
+      (
+        record_frame.run_if(resource_exists::<Recorder>),
+        advance_replay_frame.run_if(resource_exists::<ReplayPlayer>),
+      )
+        .after(TimeSystem),
+    );
+    app.add_systems(
+      FixedFirst,
+      (
+        advance_replay_input
+          .in_set(AdvanceInputSet)
+          .run_if(resource_exists::<ReplayPlayer>),
+        record_tick
+          .after(AdvanceInputSet)
+          .run_if(resource_exists::<Recorder>),
+      )
+        .run_if(in_state(AppState::InGame)),
+    );
+    app.add_systems(
+      Update,
+      (
+        record_draft_choice.run_if(resource_exists::<Recorder>),
+        replay_draft_choice
+          .before(apply_draft_choice)
+          .run_if(resource_exists::<ReplayPlayer>),
+      )
+        .run_if(in_state(InGameSubState::Draft)),
+    );
+  }
+}
+
+/// Start playing the given replay when entering [`AppState::InGame`].
+///
+/// It has to be called in the frame before the state transition,
+/// since the frame timing of the transition frame is part of the replay.
+pub fn start_replay(commands: &mut Commands, replay: &Replay) {
+  let Some(first_frame) = replay.frames.first() else {
+    return;
+  };
+
+  commands.insert_resource(NextRunSeed(Some(replay.seed)));
+  commands.insert_resource(replay.mode);
+  commands.insert_resource(RunUpgrades(replay.upgrades));
+  commands.insert_resource(CurrentLevel(replay.level));
+  commands.insert_resource(InputSource::Replay);
+  commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_nanos(
+    *first_frame as u64,
+  )));
+  commands.insert_resource(ReplayPlayer {
+    replay: replay.clone(),
+    frame: 1,
+    tick: 0,
+    repeat: 0,
+    draft: 0,
+  });
+}
+
+/// Fixed ticks left over from the previous state would run at a different time
+/// in the replay, so every run starts with an empty fixed timestep accumulator.
+fn discard_fixed_overstep(mut time: ResMut<Time<Fixed>>) {
+  let overstep = time.overstep();
+  time.discard_overstep(overstep);
+}
+
+fn start_recording(
+  mut commands: Commands,
+  rng: Res<GameRng>,
+  mode: Res<GameMode>,
+  run_upgrades: Res<RunUpgrades>,
+  current_level: Res<CurrentLevel>,
+  time: Res<Time<Real>>,
+) {
+  let mut replay = Replay {
+    seed: rng.seed(),
+    mode: *mode,
+    upgrades: run_upgrades.0,
+    level: current_level.0,
+    ..Default::default()
+  };
+  // The transition frame is already running, its timing is recorded here
+  replay.push_frame(time.delta());
+
+  commands.insert_resource(Recorder(replay));
+}
+
+fn record_frame(mut recorder: ResMut<Recorder>, time: Res<Time<Real>>) {
+  recorder.0.push_frame(time.delta());
+}
+
+fn record_tick(mut recorder: ResMut<Recorder>, input: Res<PlayerInput>) {
+  recorder.0.push_tick(input.frame);
+}
+
+fn record_draft_choice(
+  mut recorder: ResMut<Recorder>,
+  mut chosen_events: EventReader<DraftChosenEvent>,
+) {
+  let frame = recorder.0.frames.len() as u32;
+  for DraftChosenEvent(choice) in chosen_events.read() {
+    recorder.0.drafts.push((frame, *choice));
+  }
+}
+
+fn stop_recording(mut commands: Commands, recorder: Res<Recorder>) {
+  #[cfg(not(target_arch = "wasm32"))]
+  save_replay(&recorder.0);
+
+  commands.insert_resource(LastReplay(recorder.0.clone()));
+  commands.remove_resource::<Recorder>();
+}
+
+/// Save the replay to `replays/last.ron`, so it can be attached to bug reports.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_replay(replay: &Replay) {
+  let path = std::path::Path::new("replays").join("last.ron");
+
+  let result = ron::to_string(replay)
+    .map_err(|err| err.to_string())
+    .and_then(|content| {
+      std::fs::create_dir_all("replays")
+        .and_then(|_| std::fs::write(&path, content))
+        .map_err(|err| err.to_string())
+    });
+
+  match result {
+    Ok(_) => info!("Replay saved to {}", path.display()),
+    Err(err) => warn!("Could not save the replay: {}", err),
+  }
+}
+
+/// Set the timing of the next frame.
+fn advance_replay_frame(
+  mut player: ResMut<ReplayPlayer>,
+  mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) {
+  *time_update_strategy = match player.replay.frames.get(player.frame) {
+    Some(delta) => TimeUpdateStrategy::ManualDuration(Duration::from_nanos(*delta as u64)),
+    None => TimeUpdateStrategy::Automatic,
+  };
+  player.frame += 1;
+}
+
+fn advance_replay_input(mut player: ResMut<ReplayPlayer>, mut input: ResMut<PlayerInput>) {
+  let Some((count, frame)) = player.replay.ticks.get(player.tick).copied() else {
+    // The replay is over, release every input
+    input.advance(InputFrame::default());
+    return;
+  };
+
+  input.advance(frame);
+
+  player.repeat += 1;
+  if player.repeat >= count {
+    player.tick += 1;
+    player.repeat = 0;
+  }
+}
+
+/// Pick the recorded card in the frame it was picked in.
+fn replay_draft_choice(
+  mut player: ResMut<ReplayPlayer>,
+  mut chosen_events: EventWriter<DraftChosenEvent>,
+) {
+  // The frame counter is one frame ahead of the recorded frames
+  let frame = player.frame as u32 - 1;
+  let choice = match player.replay.drafts.get(player.draft) {
+    Some((recorded, choice)) if *recorded <= frame => *choice,
+    Some(_) => return,
+    // Replays recorded before drafts existed pick the first card
+    None => 0,
+  };
+
+  player.draft += 1;
+  chosen_events.send(DraftChosenEvent(choice));
+}
+
+fn stop_replay(mut commands: Commands) {
+  commands.insert_resource(InputSource::Live);
+  commands.insert_resource(TimeUpdateStrategy::Automatic);
+  commands.remove_resource::<ReplayPlayer>();
+}
+
+fn spawn_replay_label(mut commands: Commands, ui: Res<UiAssets>) {
+  commands.spawn((
+    StateDespawnMarker,
+    TextBundle::from_section(
+      "Replay",
+      TextStyle {
+        font: ui.font_mono.clone(),
+        font_size: 24.,
+        color: colors::RED_400,
+      },
+    )
+    .with_style(Style {
+      position_type: PositionType::Absolute,
+      top: Val::Px(16.),
+      left: Val::Px(16.),
+      ..Default::default()
+    }),
+  ));
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::system::RunSystemOnce;
+
+  use super::*;
+
+  fn replay_world(drafts: Vec<(u32, usize)>, frame: usize) -> World {
+    let mut world = World::new();
+    world.init_resource::<Events<DraftChosenEvent>>();
+    world.insert_resource(ReplayPlayer {
+      replay: Replay {
+        drafts,
+        ..Default::default()
+      },
+      frame,
+      tick: 0,
+      repeat: 0,
+      draft: 0,
+    });
+    world
+  }
+
+  fn chosen(world: &mut World) -> Vec<usize> {
+    world
+      .resource_mut::<Events<DraftChosenEvent>>()
+      .drain()
+      .map(|DraftChosenEvent(choice)| choice)
+      .collect()
+  }
+
+  #[test]
+  fn records_draft_choice_frame() {
+    let mut world = World::new();
+    world.init_resource::<Events<DraftChosenEvent>>();
+    world.insert_resource(Recorder(Replay {
+      frames: vec![0; 3],
+      ..Default::default()
+    }));
+    world.send_event(DraftChosenEvent(2));
+    world.run_system_once(record_draft_choice);
+
+    assert_eq!(world.resource::<Recorder>().0.drafts, vec![(3, 2)]);
+  }
+
+  #[test]
+  fn replays_draft_choice_in_its_frame() {
+    let mut world = replay_world(vec![(5, 1)], 5);
+    world.run_system_once(replay_draft_choice);
+    assert!(chosen(&mut world).is_empty());
+
+    world.resource_mut::<ReplayPlayer>().frame = 6;
+    world.run_system_once(replay_draft_choice);
+    assert_eq!(chosen(&mut world), vec![1]);
+    assert_eq!(world.resource::<ReplayPlayer>().draft, 1);
+  }
+
+  #[test]
+  fn old_replays_pick_first_card() {
+    let mut world = replay_world(Vec::new(), 1);
+    world.run_system_once(replay_draft_choice);
+    assert_eq!(chosen(&mut world), vec![0]);
+  }
+}
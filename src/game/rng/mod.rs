@@ -0,0 +1,114 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::prelude::*;
+
+/// The gameplay subsystems that draw random numbers.
+///
+/// Every stream has its own generator, so adding a random call in one
+/// subsystem doesn't shift the numbers drawn by the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+  Enemy,
+  Meteor,
+  Cycle,
+  PowerUp,
+  Draft,
+  Arena,
+}
+
+/// The seeded random number generator used by all gameplay systems.
+///
+/// It is created when entering [`AppState::InGame`] by [`init_rng`],
+/// so the same seed always produces the same run. The streams use ChaCha,
+/// whose output is fixed, unlike `StdRng` which may change between rand versions.
+#[derive(Resource)]
+pub struct GameRng {
+  seed: u64,
+  enemy: ChaCha8Rng,
+  meteor: ChaCha8Rng,
+  cycle: ChaCha8Rng,
+  power_up: ChaCha8Rng,
+  draft: ChaCha8Rng,
+  arena: ChaCha8Rng,
+}
+
+impl GameRng {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      seed,
+      enemy: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::Enemy)),
+      meteor: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::Meteor)),
+      cycle: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::Cycle)),
+      power_up: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::PowerUp)),
+      draft: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::Draft)),
+      arena: ChaCha8Rng::seed_from_u64(stream_seed(seed, RngStream::Arena)),
+    }
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+    match stream {
+      RngStream::Enemy => &mut self.enemy,
+      RngStream::Meteor => &mut self.meteor,
+      RngStream::Cycle => &mut self.cycle,
+      RngStream::PowerUp => &mut self.power_up,
+      RngStream::Draft => &mut self.draft,
+      RngStream::Arena => &mut self.arena,
+    }
+  }
+}
+
+/// The seed of the next run.
+/// When it is `None`, a random seed is picked when the run starts.
+#[derive(Resource, Default)]
+pub struct NextRunSeed(pub Option<u64>);
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<NextRunSeed>();
+    app.add_systems(OnEnter(AppState::InGame), init_rng);
+  }
+}
+
+pub fn init_rng(mut commands: Commands, mut next_seed: ResMut<NextRunSeed>) {
+  let seed = next_seed
+    .0
+    .take()
+    .unwrap_or_else(|| rand::thread_rng().gen());
+
+  info!("Starting run with seed {}", format_seed(seed));
+
+  commands.insert_resource(GameRng::new(seed));
+}
+
+/// Format a seed the way it is shown to the player.
+pub fn format_seed(seed: u64) -> String {
+  format!("{:016X}", seed)
+}
+
+/// Parse a seed typed by the player, see [`format_seed`].
+pub fn parse_seed(seed: &str) -> Option<u64> {
+  u64::from_str_radix(seed.trim(), 16).ok()
+}
+
+/// Derive the seed of a single [`RngStream`] from the run seed,
+/// using the SplitMix64 finalizer to decorrelate the streams.
+fn stream_seed(seed: u64, stream: RngStream) -> u64 {
+  mix_seed(seed, stream as u64 + 1)
+}
+
+/// Mix the `index`-th value out of `seed` with SplitMix64.
+///
+/// Unlike [`ChaCha8Rng`], its output is fixed and never changes with the `rand` version.
+pub fn mix_seed(seed: u64, index: u64) -> u64 {
+  let mut z = seed.wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  z ^ (z >> 31)
+}
@@ -0,0 +1,239 @@
+use crate::{assets::UiAssets, prelude::*};
+
+use super::{
+  power_ups::{PowerUpEffects, PowerUpKind},
+  Score,
+};
+
+/// How long a score popup stays on screen, expressed in seconds.
+const POPUP_DURATION: f32 = 0.8;
+/// How fast a score popup rises, expressed in pixels per second.
+const POPUP_SPEED: f32 = 32.;
+/// Popups must stay below the camera, which clips anything above it, and below the indicators.
+const POPUP_Z_INDEX: f32 = CAMERA_Z_INDEX - 2.;
+
+/// What earned the points of a [`ScoreEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreSource {
+  /// An attack killed `hits` enemies at once.
+  Kill { hits: usize },
+  /// A meteor fell without hitting the player.
+  MeteorDodged,
+  /// The cycle `index` is completed.
+  CycleCompleted { index: usize },
+}
+
+/// Request to award points, the amount is computed from the [`ScoreRules`].
+#[derive(Event)]
+pub struct ScoreEvent {
+  pub source: ScoreSource,
+  /// Where the score popup is shown.
+  pub position: Vec2,
+}
+
+/// Every rule used to compute the score.
+#[derive(Resource, Debug, Clone)]
+pub struct ScoreRules {
+  /// Points of a kill are `hits ^ kill_exponent`.
+  pub kill_exponent: u32,
+  pub meteor_dodged: usize,
+  /// Points of a completed cycle are `(index + 1) ^ cycle_exponent`.
+  pub cycle_exponent: u32,
+  /// Multiplier added for every kill in the combo.
+  pub combo_step: f32,
+  pub combo_max_multiplier: f32,
+  /// Time without a kill before the combo is lost, expressed in seconds.
+  pub combo_decay: f32,
+}
+
+impl Default for ScoreRules {
+  fn default() -> Self {
+    Self {
+      kill_exponent: 3,
+      meteor_dodged: 3,
+      cycle_exponent: 3,
+      combo_step: 0.1,
+      combo_max_multiplier: 3.,
+      combo_decay: 3.,
+    }
+  }
+}
+
+impl ScoreRules {
+  pub fn points(&self, source: ScoreSource) -> usize {
+    match source {
+      ScoreSource::Kill { hits } => hits.pow(self.kill_exponent),
+      ScoreSource::MeteorDodged => self.meteor_dodged,
+      ScoreSource::CycleCompleted { index } => (index + 1).pow(self.cycle_exponent),
+    }
+  }
+
+  pub fn multiplier(&self, combo: &Combo) -> f32 {
+    (1. + combo.kills as f32 * self.combo_step).min(self.combo_max_multiplier)
+  }
+}
+
+/// Consecutive kills, each one extending the time before the combo is lost.
+#[derive(Resource, Debug, Default)]
+pub struct Combo {
+  pub kills: usize,
+  timer: Timer,
+}
+
+#[derive(Component)]
+struct ScorePopup {
+  timer: Timer,
+}
+
+#[derive(Component)]
+struct ComboText;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+  fn build(&self, app: &mut App) {
+    app.add_event::<ScoreEvent>();
+    app.init_resource::<ScoreRules>();
+    app.init_resource::<Combo>();
+
+    app.add_systems(OnEnter(AppState::InGame), (reset_combo, spawn_combo_text));
+    app.add_systems(
+      Update,
+      (decay_combo, animate_score_popups).run_if(in_state(AppState::InGame)),
+    );
+    // The events are sent from `Update`, they are all applied in the same frame
+    app.add_systems(
+      PostUpdate,
+      (apply_score_events, update_combo_text)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+  *combo = Combo::default();
+}
+
+fn decay_combo(mut combo: ResMut<Combo>, time: Res<Time>) {
+  combo.timer.tick(time.delta());
+  if combo.timer.just_finished() {
+    combo.kills = 0;
+  }
+}
+
+fn apply_score_events(
+  mut commands: Commands,
+  mut events: EventReader<ScoreEvent>,
+  mut score: ResMut<Score>,
+  mut combo: ResMut<Combo>,
+  rules: Res<ScoreRules>,
+  effects_query: Query<&PowerUpEffects>,
+  ui: Res<UiAssets>,
+) {
+  let double_score = effects_query
+    .iter()
+    .any(|effects| effects.is_active(PowerUpKind::DoubleScore));
+
+  for event in events.read() {
+    let multiplier = match double_score {
+      true => rules.multiplier(&combo) * 2.,
+      false => rules.multiplier(&combo),
+    };
+    let points = (rules.points(event.source) as f32 * multiplier).round() as usize;
+    score.0 += points;
+
+    if let ScoreSource::Kill { hits } = event.source {
+      combo.kills += hits;
+      combo.timer = Timer::from_seconds(rules.combo_decay, TimerMode::Once);
+    }
+
+    let label = match multiplier > 1. {
+      true => format!("+{} x{:.1}", points, multiplier),
+      false => format!("+{}", points),
+    };
+
+    spawn_popup(&mut commands, &ui, event.position, label);
+  }
+}
+
+/// Spawn a text at a world position that rises and fades out.
+pub fn spawn_popup(commands: &mut Commands, ui: &UiAssets, position: Vec2, label: String) {
+  commands.spawn((
+    StateDespawnMarker,
+    ScorePopup {
+      timer: Timer::from_seconds(POPUP_DURATION, TimerMode::Once),
+    },
+    Text2dBundle {
+      text: Text::from_section(
+        label,
+        TextStyle {
+          font: ui.font_mono.clone(),
+          font_size: 16.,
+          color: colors::PRIMARY_100,
+        },
+      ),
+      transform: Transform::from_translation(position.extend(POPUP_Z_INDEX)),
+      ..Default::default()
+    },
+  ));
+}
+
+fn animate_score_popups(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut ScorePopup, &mut Transform, &mut Text)>,
+  time: Res<Time>,
+) {
+  for (entity, mut popup, mut transform, mut text) in &mut query {
+    popup.timer.tick(time.delta());
+    if popup.timer.finished() {
+      commands.entity(entity).despawn_recursive();
+      continue;
+    }
+
+    transform.translation.y += POPUP_SPEED * time.delta_seconds();
+
+    let alpha = 1. - popup.timer.fraction();
+    for section in text.sections.iter_mut() {
+      section.style.color.set_alpha(alpha);
+    }
+  }
+}
+
+fn spawn_combo_text(mut commands: Commands, ui: Res<UiAssets>) {
+  commands.spawn((
+    StateDespawnMarker,
+    ComboText,
+    TextBundle::from_section(
+      String::new(),
+      TextStyle {
+        font: ui.font_mono.clone(),
+        font_size: 20.,
+        color: colors::RED_400,
+      },
+    )
+    .with_style(Style {
+      position_type: PositionType::Absolute,
+      top: Val::Px(110.),
+      right: Val::Px(16.),
+      ..Default::default()
+    }),
+  ));
+}
+
+fn update_combo_text(
+  mut query: Query<&mut Text, With<ComboText>>,
+  combo: Res<Combo>,
+  rules: Res<ScoreRules>,
+) {
+  let label = match combo.kills {
+    0 => String::new(),
+    kills => format!("Combo {} x{:.1}", kills, rules.multiplier(&combo)),
+  };
+
+  for mut text in &mut query {
+    if text.sections[0].value != label {
+      text.sections[0].value = label.clone();
+    }
+  }
+}
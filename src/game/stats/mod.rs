@@ -0,0 +1,140 @@
+use crate::prelude::*;
+
+use super::{
+  attack::{AttackEvent, AttackResolvedEvent},
+  cycle::{CycleCompletedEvent, MeteorImpactEvent},
+};
+
+/// Statistics of the current run, shown on the game over screen.
+///
+/// They are collected from the events sent by the gameplay modules.
+#[derive(Resource, Debug, Clone)]
+pub struct RunStats {
+  pub aqua_kills: usize,
+  pub red_kills: usize,
+  pub green_kills: usize,
+  pub largest_multi_kill: usize,
+  pub valid_attacks: usize,
+  pub invalid_attacks: usize,
+  pub dodges: usize,
+  /// Enemies knocked into a pit, they are part of the kills as well.
+  pub pit_kills: usize,
+  pub meteors_survived: usize,
+  pub cycle: usize,
+  /// Time spent alive, expressed in seconds.
+  pub time_alive: f32,
+  pub cause_of_death: Option<DeathCause>,
+}
+
+impl Default for RunStats {
+  fn default() -> Self {
+    Self {
+      aqua_kills: 0,
+      red_kills: 0,
+      green_kills: 0,
+      largest_multi_kill: 0,
+      valid_attacks: 0,
+      invalid_attacks: 0,
+      dodges: 0,
+      pit_kills: 0,
+      meteors_survived: 0,
+      cycle: 1,
+      time_alive: 0.,
+      cause_of_death: None,
+    }
+  }
+}
+
+impl RunStats {
+  pub fn kills(&self) -> usize {
+    self.aqua_kills + self.red_kills + self.green_kills
+  }
+
+  pub fn shapes_drawn(&self) -> usize {
+    self.valid_attacks + self.invalid_attacks
+  }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<RunStats>();
+    app.add_systems(OnEnter(AppState::InGame), reset_stats);
+    // Collected after the gameplay systems sent their events,
+    // so that the stats are complete before leaving the game
+    app.add_systems(
+      PostUpdate,
+      (
+        count_kills,
+        count_attacks,
+        count_dodges,
+        count_meteors,
+        count_cycles,
+        record_death,
+        tick_time_alive,
+      )
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+  *stats = RunStats::default();
+}
+
+fn count_kills(mut stats: ResMut<RunStats>, mut events: EventReader<EnemyKilledEvent>) {
+  for event in events.read() {
+    if event.cause == EnemyDeathCause::Pit {
+      stats.pit_kills += 1;
+    }
+    match event.variant {
+      EnemyVariant::Aqua => stats.aqua_kills += 1,
+      EnemyVariant::Red => stats.red_kills += 1,
+      EnemyVariant::Green => stats.green_kills += 1,
+    }
+  }
+}
+
+fn count_attacks(
+  mut stats: ResMut<RunStats>,
+  mut attack_events: EventReader<AttackEvent>,
+  mut resolved_events: EventReader<AttackResolvedEvent>,
+) {
+  for event in attack_events.read() {
+    match event.valid {
+      true => stats.valid_attacks += 1,
+      false => stats.invalid_attacks += 1,
+    }
+  }
+
+  for event in resolved_events.read() {
+    stats.largest_multi_kill = stats.largest_multi_kill.max(event.hits);
+  }
+}
+
+fn count_dodges(mut stats: ResMut<RunStats>, mut events: EventReader<PlayerDodgedEvent>) {
+  stats.dodges += events.read().count();
+}
+
+fn count_meteors(mut stats: ResMut<RunStats>, mut events: EventReader<MeteorImpactEvent>) {
+  stats.meteors_survived += events.read().filter(|event| !event.hit_player).count();
+}
+
+fn count_cycles(mut stats: ResMut<RunStats>, mut events: EventReader<CycleCompletedEvent>) {
+  for event in events.read() {
+    stats.cycle = stats.cycle.max(event.index + 1);
+  }
+}
+
+fn record_death(mut stats: ResMut<RunStats>, mut events: EventReader<PlayerDiedEvent>) {
+  for event in events.read() {
+    stats.cause_of_death.get_or_insert(event.cause);
+  }
+}
+
+fn tick_time_alive(mut stats: ResMut<RunStats>, time: Res<Time>) {
+  if stats.cause_of_death.is_none() {
+    stats.time_alive += time.delta_seconds();
+  }
+}
@@ -1,25 +1,542 @@
-use bevy::prelude::*;
-use bevy_ecs_ldtk::LdtkIntCell;
-use bevy_rapier2d::prelude::{Collider, RigidBody};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_ecs_ldtk::{
+  app::{LdtkEntityAppExt, LdtkIntCellAppExt},
+  prelude::LdtkEntity,
+  EntityInstance, GridCoords, IntGridCell, LdtkIntCell,
+};
+use bevy_ecs_tilemap::{
+  map::TilemapId,
+  tiles::{TileColor, TilePos, TileStorage},
+};
+use bevy_rapier2d::prelude::{
+  Collider, CollisionGroups, QueryFilter, RapierContext, RigidBody, Sensor,
+};
+
+use crate::{
+  app_state::AppState,
+  constants::{ATTACK_GROUP, COVER_GROUP, PLAYER_GROUP, TERRAIN_GROUP},
+};
 
 use super::TILE_SPRITE_SIZE;
 
-#[derive(Component)]
+/// The IntGrid cells and the entities of the LDtk levels.
+pub struct TilesPlugin;
+
+impl Plugin for TilesPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_event::<CoverHitEvent>();
+
+    app.register_ldtk_int_cell::<WallBundle>(1);
+    app.register_ldtk_int_cell::<TerrainBundle>(2);
+    app.register_ldtk_int_cell::<TerrainBundle>(3);
+    app.register_ldtk_int_cell::<TerrainBundle>(4);
+    app.register_ldtk_int_cell::<PitBundle>(5);
+    app.register_ldtk_int_cell::<CoverBundle>(6);
+    app.register_ldtk_entity::<PlayerStartBundle>("PlayerStart");
+    app.register_ldtk_entity::<EnemySpawnerBundle>("EnemySpawner");
+    app.register_ldtk_entity::<MeteorZoneBundle>("MeteorZone");
+
+    app.add_systems(Update, merge_wall_colliders);
+    app.add_systems(Update, damage_cover.run_if(in_state(AppState::InGame)));
+  }
+}
+
+/// A wall cell, its collider is merged with its neighbours by [`merge_wall_colliders`].
+#[derive(Component, Default)]
 pub struct Wall;
 
-#[derive(Bundle, LdtkIntCell)]
+#[derive(Bundle, LdtkIntCell, Default)]
 pub struct WallBundle {
   wall: Wall,
+}
+
+/// A rectangle of wall cells sharing a single collider.
+#[derive(Component)]
+pub struct WallCollider;
+
+/// Merge the walls of every spawned layer into as few rectangles as possible.
+///
+/// The colliders are children of the layer, so they are rebuilt with the level.
+fn merge_wall_colliders(
+  mut commands: Commands,
+  wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
+) {
+  let mut layers: HashMap<Entity, Vec<IVec2>> = HashMap::new();
+  for (coords, parent) in &wall_query {
+    layers
+      .entry(parent.get())
+      .or_default()
+      .push(IVec2::new(coords.x, coords.y));
+  }
+
+  for (layer, cells) in layers {
+    commands.entity(layer).with_children(|parent| {
+      for (min, max) in merge_cells(cells) {
+        let size = (max - min + IVec2::ONE).as_vec2() * TILE_SPRITE_SIZE;
+        // Tiles are centered on their grid coordinates within the layer
+        let center = (min + max).as_vec2() * TILE_SPRITE_SIZE / 2.;
+        parent.spawn((
+          WallCollider,
+          Collider::cuboid(size.x / 2., size.y / 2.),
+          RigidBody::Fixed,
+          TransformBundle::from_transform(Transform::from_translation(center.extend(0.))),
+        ));
+      }
+    });
+  }
+}
+
+/// Greedy meshing of the cells: grow every rectangle to the right, then upwards.
+/// Returns the bottom-left and the top-right cells of each rectangle.
+fn merge_cells(cells: Vec<IVec2>) -> Vec<(IVec2, IVec2)> {
+  let Some(min) = cells.iter().copied().reduce(IVec2::min) else {
+    return Vec::new();
+  };
+  let size = cells.iter().copied().fold(min, IVec2::max) - min + IVec2::ONE;
+  let index = |cell: IVec2| ((cell.y - min.y) * size.x + cell.x - min.x) as usize;
+
+  let mut free = vec![false; (size.x * size.y) as usize];
+  for cell in &cells {
+    free[index(*cell)] = true;
+  }
+
+  let mut rects = Vec::new();
+  for y in min.y..min.y + size.y {
+    for x in min.x..min.x + size.x {
+      let start = IVec2::new(x, y);
+      if !free[index(start)] {
+        continue;
+      }
+
+      let mut end = start;
+      while end.x + 1 < min.x + size.x && free[index(IVec2::new(end.x + 1, y))] {
+        end.x += 1;
+      }
+      while end.y + 1 < min.y + size.y
+        && (start.x..=end.x).all(|x| free[index(IVec2::new(x, end.y + 1))])
+      {
+        end.y += 1;
+      }
+
+      for y in start.y..=end.y {
+        for x in start.x..=end.x {
+          free[index(IVec2::new(x, y))] = false;
+        }
+      }
+      rects.push((start, end));
+    }
+  }
+
+  rects
+}
+
+/// Ground that changes how the player and the enemies behave when standing on it.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+  /// Slows the player down.
+  Water,
+  /// Slows the player down, less than water.
+  Mud,
+  /// Hurts the player, unless dodging over it.
+  Spikes,
+  /// Blocks the player and kills the enemies pushed in.
+  Pit,
+}
+
+impl From<IntGridCell> for Terrain {
+  fn from(cell: IntGridCell) -> Self {
+    match cell.value {
+      2 => Terrain::Water,
+      3 => Terrain::Mud,
+      4 => Terrain::Spikes,
+      5 => Terrain::Pit,
+      value => panic!("IntGrid value {} is not a terrain", value),
+    }
+  }
+}
+
+impl Terrain {
+  /// Multiplier of the player speed.
+  pub fn speed(&self) -> f32 {
+    match self {
+      Terrain::Water => 0.5,
+      Terrain::Mud => 0.7,
+      Terrain::Spikes | Terrain::Pit => 1.,
+    }
+  }
+}
+
+/// A tile that is walked over, only found with [`terrain_at`].
+#[derive(Bundle)]
+pub struct SensorTileBundle {
+  collider: Collider,
+  sensor: Sensor,
+  groups: CollisionGroups,
+}
+
+impl Default for SensorTileBundle {
+  fn default() -> Self {
+    Self {
+      collider: Collider::cuboid(TILE_SPRITE_SIZE * 0.5, TILE_SPRITE_SIZE * 0.5),
+      sensor: Sensor,
+      groups: CollisionGroups::new(TERRAIN_GROUP, TERRAIN_GROUP),
+    }
+  }
+}
+
+/// Water, mud or spikes, depending on the IntGrid value of the cell.
+#[derive(Bundle, LdtkIntCell)]
+pub struct TerrainBundle {
+  #[from_int_grid_cell]
+  terrain: Terrain,
+  #[with(sensor_tile)]
+  tile: SensorTileBundle,
+}
+
+fn sensor_tile(_: IntGridCell) -> SensorTileBundle {
+  SensorTileBundle::default()
+}
+
+/// Pits are solid for the player. The enemies stop at their edge, unless knocked back in.
+#[derive(Bundle, LdtkIntCell)]
+pub struct PitBundle {
+  terrain: Terrain,
   collider: Collider,
   rb: RigidBody,
+  groups: CollisionGroups,
 }
 
-impl Default for WallBundle {
+impl Default for PitBundle {
   fn default() -> Self {
     Self {
-      wall: Wall,
+      terrain: Terrain::Pit,
       collider: Collider::cuboid(TILE_SPRITE_SIZE * 0.5, TILE_SPRITE_SIZE * 0.5),
       rb: RigidBody::Fixed,
+      groups: CollisionGroups::new(TERRAIN_GROUP, TERRAIN_GROUP | PLAYER_GROUP),
+    }
+  }
+}
+
+/// How many hits a cover tile takes before breaking.
+const COVER_HIT_POINTS: u32 = 3;
+/// Opacity of a cover tile about to break.
+const COVER_MIN_ALPHA: f32 = 0.3;
+
+/// Low walls blocking the enemy attacks and the meteors, see [`cover_between`].
+#[derive(Component)]
+pub struct Cover {
+  hit_points: u32,
+}
+
+#[derive(Bundle, LdtkIntCell)]
+pub struct CoverBundle {
+  cover: Cover,
+  collider: Collider,
+  rb: RigidBody,
+  groups: CollisionGroups,
+}
+
+impl Default for CoverBundle {
+  fn default() -> Self {
+    Self {
+      cover: Cover {
+        hit_points: COVER_HIT_POINTS,
+      },
+      collider: Collider::cuboid(TILE_SPRITE_SIZE * 0.5, TILE_SPRITE_SIZE * 0.5),
+      rb: RigidBody::Fixed,
+      // Enemy strikes overlapping the cover damage it
+      groups: CollisionGroups::new(COVER_GROUP, COVER_GROUP | PLAYER_GROUP | ATTACK_GROUP),
+    }
+  }
+}
+
+/// Sent when a cover tile blocks an enemy strike or a meteor.
+#[derive(Event)]
+pub struct CoverHitEvent(pub Entity);
+
+/// The terrain under the given point, if any.
+pub fn terrain_at(
+  rapier_context: &RapierContext,
+  terrain_query: &Query<&Terrain>,
+  point: Vec2,
+) -> Option<Terrain> {
+  let filter = QueryFilter::new().groups(CollisionGroups::new(TERRAIN_GROUP, TERRAIN_GROUP));
+  let mut terrain = None;
+  rapier_context.intersections_with_point(point, filter, |entity| {
+    terrain = terrain_query.get(entity).ok().copied();
+    terrain.is_none()
+  });
+  terrain
+}
+
+/// The first cover tile standing between the two points, if any.
+pub fn cover_between(rapier_context: &RapierContext, from: Vec2, to: Vec2) -> Option<Entity> {
+  let filter = QueryFilter::new().groups(CollisionGroups::new(COVER_GROUP, COVER_GROUP));
+  rapier_context
+    .cast_ray(from, to - from, 1., true, filter)
+    .map(|(entity, _)| entity)
+}
+
+/// The cover tile under the given point, if any.
+pub fn cover_at(rapier_context: &RapierContext, point: Vec2) -> Option<Entity> {
+  let filter = QueryFilter::new().groups(CollisionGroups::new(COVER_GROUP, COVER_GROUP));
+  let mut cover = None;
+  rapier_context.intersections_with_point(point, filter, |entity| {
+    cover = Some(entity);
+    false
+  });
+  cover
+}
+
+/// Cover tiles fade with every hit and are removed from the tilemap when broken.
+fn damage_cover(
+  mut commands: Commands,
+  mut hit_events: EventReader<CoverHitEvent>,
+  mut cover_query: Query<(&mut Cover, &mut TileColor, &TilePos, &TilemapId)>,
+  mut storage_query: Query<&mut TileStorage>,
+) {
+  for CoverHitEvent(entity) in hit_events.read() {
+    let Ok((mut cover, mut color, tile_pos, tilemap_id)) = cover_query.get_mut(*entity) else {
+      continue;
+    };
+
+    // Broken tiles wait for their despawn
+    if cover.hit_points == 0 {
+      continue;
+    }
+
+    cover.hit_points -= 1;
+    if cover.hit_points > 0 {
+      let health = cover.hit_points as f32 / COVER_HIT_POINTS as f32;
+      color.0 = color
+        .0
+        .with_alpha(COVER_MIN_ALPHA + (1. - COVER_MIN_ALPHA) * health);
+      continue;
+    }
+
+    if let Ok(mut storage) = storage_query.get_mut(tilemap_id.0) {
+      storage.remove(tile_pos);
+    }
+    commands.entity(*entity).despawn_recursive();
+  }
+}
+
+/// Where the player is placed when the level spawns.
+#[derive(Component, Default)]
+pub struct PlayerStart;
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct PlayerStartBundle {
+  player_start: PlayerStart,
+}
+
+/// Enemies spawn within the radius of a random spawner of the level.
+#[derive(Component, Default)]
+pub struct EnemySpawner {
+  pub radius: f32,
+}
+
+impl From<&EntityInstance> for EnemySpawner {
+  fn from(entity_instance: &EntityInstance) -> Self {
+    Self {
+      radius: entity_instance.width.min(entity_instance.height) as f32 / 2.,
+    }
+  }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct EnemySpawnerBundle {
+  #[from_entity_instance]
+  enemy_spawner: EnemySpawner,
+}
+
+/// Meteors only fall within the meteor zones of the level, when it has any.
+#[derive(Component, Default)]
+pub struct MeteorZone {
+  pub half_size: Vec2,
+}
+
+impl MeteorZone {
+  /// The point of the zone centered in `center` closest to `point`.
+  pub fn clamp(&self, center: Vec2, point: Vec2) -> Vec2 {
+    point.clamp(center - self.half_size, center + self.half_size)
+  }
+}
+
+impl From<&EntityInstance> for MeteorZone {
+  fn from(entity_instance: &EntityInstance) -> Self {
+    Self {
+      half_size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32) / 2.,
+    }
+  }
+}
+
+#[derive(Bundle, LdtkEntity, Default)]
+pub struct MeteorZoneBundle {
+  #[from_entity_instance]
+  meteor_zone: MeteorZone,
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::{ecs::system::RunSystemOnce, utils::HashSet};
+  use bevy_rapier2d::plugin::{NoUserData, RapierPhysicsPlugin};
+
+  use super::*;
+
+  /// Merge the cells and check that the rectangles cover each of them exactly once.
+  fn assert_exact_cover(cells: &[IVec2]) -> Vec<(IVec2, IVec2)> {
+    let rects = merge_cells(cells.to_vec());
+
+    let mut covered = HashSet::new();
+    for (min, max) in &rects {
+      assert!(
+        min.x <= max.x && min.y <= max.y,
+        "inverted rectangle {min} {max}"
+      );
+      for y in min.y..=max.y {
+        for x in min.x..=max.x {
+          assert!(covered.insert(IVec2::new(x, y)), "overlap at {x} {y}");
+        }
+      }
     }
+
+    let expected: HashSet<_> = cells.iter().copied().collect();
+    assert_eq!(covered, expected);
+
+    rects
+  }
+
+  #[test]
+  fn merges_empty_input() {
+    assert!(assert_exact_cover(&[]).is_empty());
+  }
+
+  #[test]
+  fn merges_single_cell() {
+    let cell = IVec2::new(-3, 7);
+    assert_eq!(assert_exact_cover(&[cell]), vec![(cell, cell)]);
+  }
+
+  #[test]
+  fn merges_l_shape() {
+    let cells = [
+      IVec2::new(0, 0),
+      IVec2::new(1, 0),
+      IVec2::new(2, 0),
+      IVec2::new(0, 1),
+      IVec2::new(0, 2),
+    ];
+    assert_eq!(assert_exact_cover(&cells).len(), 2);
+  }
+
+  #[test]
+  fn merges_hollow_square() {
+    let cells: Vec<_> = (-2..=2)
+      .flat_map(|y| (-2..=2).map(move |x| IVec2::new(x, y)))
+      .filter(|cell| cell.x.abs() == 2 || cell.y.abs() == 2)
+      .collect();
+    assert_eq!(assert_exact_cover(&cells).len(), 4);
+  }
+
+  /// A world with physics and the given tiles, stepped once so the colliders can be queried.
+  fn tiles_app(spawn: impl FnOnce(&mut World)) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      TransformPlugin,
+      HierarchyPlugin,
+      RapierPhysicsPlugin::<NoUserData>::default(),
+    ));
+    spawn(app.world_mut());
+    app.update();
+    app
+  }
+
+  fn spawn_terrain(world: &mut World, terrain: Terrain, position: Vec2) {
+    world.spawn((
+      terrain,
+      SensorTileBundle::default(),
+      TransformBundle::from_transform(Transform::from_translation(position.extend(0.))),
+    ));
+  }
+
+  fn spawn_cover(world: &mut World, position: Vec2) -> Entity {
+    world
+      .spawn((
+        CoverBundle::default(),
+        TransformBundle::from_transform(Transform::from_translation(position.extend(0.))),
+      ))
+      .id()
+  }
+
+  fn terrain(app: &mut App, point: Vec2) -> Option<Terrain> {
+    app.world_mut().run_system_once(
+      move |rapier_context: Res<RapierContext>, terrain_query: Query<&Terrain>| {
+        terrain_at(&rapier_context, &terrain_query, point)
+      },
+    )
+  }
+
+  #[test]
+  fn terrain_from_int_grid() {
+    let terrain = |value| Terrain::from(IntGridCell { value });
+    assert_eq!(terrain(2), Terrain::Water);
+    assert_eq!(terrain(3), Terrain::Mud);
+    assert_eq!(terrain(4), Terrain::Spikes);
+    assert_eq!(terrain(5), Terrain::Pit);
+  }
+
+  #[test]
+  fn finds_terrain_under_point() {
+    let mut app = tiles_app(|world| {
+      spawn_terrain(world, Terrain::Water, Vec2::ZERO);
+      spawn_terrain(world, Terrain::Mud, Vec2::new(TILE_SPRITE_SIZE, 0.));
+    });
+
+    assert_eq!(terrain(&mut app, Vec2::ZERO), Some(Terrain::Water));
+    assert_eq!(
+      terrain(&mut app, Vec2::new(TILE_SPRITE_SIZE, 0.)),
+      Some(Terrain::Mud)
+    );
+    assert_eq!(terrain(&mut app, Vec2::new(0., TILE_SPRITE_SIZE)), None);
+  }
+
+  #[test]
+  fn cover_is_not_terrain() {
+    let mut app = tiles_app(|world| {
+      spawn_cover(world, Vec2::ZERO);
+    });
+
+    assert_eq!(terrain(&mut app, Vec2::ZERO), None);
+  }
+
+  #[test]
+  fn finds_cover() {
+    let cover = Vec2::new(2. * TILE_SPRITE_SIZE, 0.);
+    let mut entity = Entity::PLACEHOLDER;
+    let mut app = tiles_app(|world| {
+      entity = spawn_cover(world, cover);
+      spawn_terrain(world, Terrain::Spikes, Vec2::ZERO);
+    });
+
+    let (at, between, beside) =
+      app
+        .world_mut()
+        .run_system_once(move |rapier_context: Res<RapierContext>| {
+          let above = Vec2::new(0., 2. * TILE_SPRITE_SIZE);
+          (
+            (
+              cover_at(&rapier_context, cover),
+              cover_at(&rapier_context, Vec2::ZERO),
+            ),
+            cover_between(&rapier_context, Vec2::ZERO, 2. * cover),
+            cover_between(&rapier_context, above, above + 2. * cover),
+          )
+        });
+
+    assert_eq!(at, (Some(entity), None));
+    assert_eq!(between, Some(entity));
+    assert_eq!(beside, None);
   }
 }
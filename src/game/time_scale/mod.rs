@@ -0,0 +1,114 @@
+use crate::prelude::*;
+
+use super::attack::Attack;
+
+/// Speed of the game while an attack is being drawn.
+const DRAWING_SPEED: f32 = 0.4;
+/// How fast the slow motion kicks in and wears off, in speed per second.
+const SLOW_MOTION_EASING: f32 = 4.;
+
+/// The speed of [`Time<Virtual>`], driving everything that runs on game time:
+/// enemy and cycle timers, the fixed ticks moving the player and the meteors, the physics.
+#[derive(Resource)]
+pub struct TimeScale {
+  /// The speed outside of hit-stops, eased towards the slow motion when drawing.
+  base: f32,
+  /// The ongoing hit-stop, timed in real time, with its speed.
+  hit_stop: Option<(Timer, f32)>,
+}
+
+impl Default for TimeScale {
+  fn default() -> Self {
+    Self {
+      base: 1.,
+      hit_stop: None,
+    }
+  }
+}
+
+impl TimeScale {
+  fn speed(&self) -> f32 {
+    match &self.hit_stop {
+      Some((_, speed)) => *speed,
+      None => self.base,
+    }
+  }
+}
+
+/// Freeze or slow down the game for a moment, expressed in real seconds.
+/// A speed of 0 freezes the game.
+#[derive(Event)]
+pub struct HitStopEvent {
+  pub duration: f32,
+  pub speed: f32,
+}
+
+pub struct TimeScalePlugin;
+
+impl Plugin for TimeScalePlugin {
+  fn build(&self, app: &mut App) {
+    app.init_resource::<TimeScale>();
+    app.add_event::<HitStopEvent>();
+
+    app.add_systems(OnEnter(AppState::InGame), reset_time_scale);
+    app.add_systems(OnExit(AppState::InGame), reset_time_scale);
+    app.add_systems(
+      PostUpdate,
+      (update_hit_stop, slow_down_while_drawing, apply_time_scale)
+        .chain()
+        .run_if(in_state(AppState::InGame)),
+    );
+  }
+}
+
+fn reset_time_scale(mut time_scale: ResMut<TimeScale>, mut time: ResMut<Time<Virtual>>) {
+  *time_scale = TimeScale::default();
+  time.set_relative_speed(1.);
+}
+
+/// The slowest hit-stop wins, and lasts as long as the longest one.
+/// Hit-stops are timed in real time, they would never end while the game is frozen otherwise.
+fn update_hit_stop(
+  mut hit_stop_events: EventReader<HitStopEvent>,
+  mut time_scale: ResMut<TimeScale>,
+  time: Res<Time<Real>>,
+) {
+  if let Some((timer, _)) = &mut time_scale.hit_stop {
+    if timer.tick(time.delta()).finished() {
+      time_scale.hit_stop = None;
+    }
+  }
+
+  for event in hit_stop_events.read() {
+    let (duration, speed) = match &time_scale.hit_stop {
+      Some((timer, speed)) => (
+        timer.remaining_secs().max(event.duration),
+        speed.min(event.speed),
+      ),
+      None => (event.duration, event.speed),
+    };
+
+    time_scale.hit_stop = Some((Timer::from_seconds(duration, TimerMode::Once), speed));
+  }
+}
+
+fn slow_down_while_drawing(
+  attack_query: Query<(), With<Attack>>,
+  mut time_scale: ResMut<TimeScale>,
+  time: Res<Time<Real>>,
+) {
+  let target = match attack_query.is_empty() {
+    true => 1.,
+    false => DRAWING_SPEED,
+  };
+
+  let step = SLOW_MOTION_EASING * time.delta_seconds();
+  time_scale.base += (target - time_scale.base).clamp(-step, step);
+}
+
+fn apply_time_scale(time_scale: Res<TimeScale>, mut time: ResMut<Time<Virtual>>) {
+  let speed = time_scale.speed();
+  if time.relative_speed() != speed {
+    time.set_relative_speed(speed);
+  }
+}
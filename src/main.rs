@@ -1,10 +1,15 @@
 use assets::AssetsLoadingPlugin;
+use bevy::window::WindowResolution;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_particle_systems::ParticleSystemPlugin;
 use bevy_rapier2d::plugin::{NoUserData, RapierPhysicsPlugin};
 use game::GamePlugin;
 use iyes_progress::prelude::*;
-use screens::{game_over::GameOverPlugin, loading::LoadscreenPlugin, main_menu::MainMenuPlugin};
+use screens::{
+  achievements::AchievementsScreenPlugin, game_over::GameOverPlugin,
+  level_select::LevelSelectScreenPlugin, loading::LoadscreenPlugin, main_menu::MainMenuPlugin,
+  settings::SettingsScreenPlugin, upgrades::UpgradesScreenPlugin,
+};
 
 use crate::prelude::*;
 
@@ -16,6 +21,7 @@ mod dev_tools;
 mod game;
 mod prelude;
 mod screens;
+mod storage;
 mod utils;
 
 fn main() -> AppExit {
@@ -31,9 +37,8 @@ fn main() -> AppExit {
       #[cfg(not(target_arch = "wasm32"))]
       title: "Take Cover".into(),
       present_mode: bevy::window::PresentMode::Fifo,
-      // TODO: handle fixed resolution
-      // resizable: false,
-      // resolution: WindowResolution::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+      // The game is drawn at a fixed resolution, letterboxed in the window
+      resolution: WindowResolution::new(VIRTUAL_WIDTH * 2., VIRTUAL_HEIGHT * 2.),
       prevent_default_event_handling: true,
       #[cfg(target_arch = "wasm32")]
       fit_canvas_to_parent: true,
@@ -90,6 +95,10 @@ fn main() -> AppExit {
     GameOverPlugin {
       state: AppState::GameOver,
     },
+    AchievementsScreenPlugin,
+    UpgradesScreenPlugin,
+    LevelSelectScreenPlugin,
+    SettingsScreenPlugin,
     AssetsLoadingPlugin,
   ));
 
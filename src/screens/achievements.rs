@@ -0,0 +1,105 @@
+use crate::{
+  app_state::MainMenuSubState,
+  assets::UiAssets,
+  game::achievements::{Achievement, Achievements},
+  prelude::*,
+};
+
+use super::spawn_menu_overlay;
+
+/// Marker for the entities of the achievements screen, drawn over the main menu.
+#[derive(Component)]
+struct AchievementsScreen;
+
+pub struct AchievementsScreenPlugin;
+
+impl Plugin for AchievementsScreenPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      OnEnter(MainMenuSubState::Achievements),
+      setup_achievements_screen,
+    );
+    app.add_systems(
+      OnExit(MainMenuSubState::Achievements),
+      despawn_all_recursive::<AchievementsScreen>,
+    );
+  }
+}
+
+fn setup_achievements_screen(
+  mut commands: Commands,
+  ui: Res<UiAssets>,
+  achievements: Res<Achievements>,
+) {
+  let list = commands
+    .spawn(NodeBundle {
+      style: Style {
+        display: Display::Grid,
+        column_gap: Val::Px(32.),
+        row_gap: Val::Px(12.),
+        align_items: AlignItems::Center,
+        grid_template_columns: vec![GridTrack::min_content(), GridTrack::flex(1.)],
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      for achievement in Achievement::ALL {
+        let unlocked = achievements.is_unlocked(achievement);
+        let color = match unlocked {
+          true => colors::RED_400,
+          false => colors::PRIMARY_300,
+        };
+
+        parent.spawn(TextBundle::from_sections([
+          TextSection::new(
+            format!("{}\n", achievement.title()),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color,
+              font_size: 24.,
+            },
+          ),
+          TextSection::new(
+            achievement.description(),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color: colors::PRIMARY_300,
+              font_size: 16.,
+            },
+          ),
+        ]));
+
+        let progress = match unlocked {
+          true => "Unlocked".to_string(),
+          false => format!(
+            "{}/{}",
+            achievements.progress(achievement),
+            achievement.target()
+          ),
+        };
+
+        parent.spawn(TextBundle::from_section(
+          progress,
+          TextStyle {
+            font: ui.font_mono.clone(),
+            color,
+            font_size: 20.,
+          },
+        ));
+      }
+    })
+    .id();
+
+  spawn_menu_overlay(
+    &mut commands,
+    &ui,
+    AchievementsScreen,
+    format!(
+      "Achievements {}/{}",
+      achievements.unlocked.len(),
+      Achievement::ALL.len()
+    ),
+    &[list],
+  );
+}
@@ -1,6 +1,27 @@
-use crate::{assets::UiAssets, game::Score, prelude::*};
+use crate::{
+  assets::UiAssets,
+  game::{
+    daily::{DailyBest, NewDailyBest},
+    leaderboard::{Leaderboard, LeaderboardPlacement, NAME_LENGTH},
+    mode::GameMode,
+    progression::{LastEarnings, Progression},
+    replay::{start_replay, LastReplay},
+    rng::{format_seed, GameRng, NextRunSeed},
+    stats::RunStats,
+    Score,
+  },
+  prelude::*,
+};
 
-use super::main_menu::StateOnPress;
+use bevy::input::{
+  keyboard::{Key, KeyboardInput},
+  ButtonState,
+};
+
+use super::{
+  leaderboard::{spawn_leaderboard, update_leaderboard_rows},
+  main_menu::{action_on_press, StateOnPress},
+};
 
 pub struct GameOverPlugin<S: States> {
   pub state: S,
@@ -9,10 +30,160 @@ pub struct GameOverPlugin<S: States> {
 impl<S: States> Plugin for GameOverPlugin<S> {
   fn build(&self, #[allow(unused_variables)] app: &mut App) {
     app.add_systems(OnEnter(self.state.clone()), setup_game_over);
+    app.add_systems(
+      Update,
+      (retry_seed_on_press, watch_replay_on_press)
+        .before(action_on_press::<AppState>)
+        .run_if(in_state(self.state.clone())),
+    );
+    app.add_systems(
+      Update,
+      (type_name, update_name_label, update_leaderboard_rows)
+        .chain()
+        .run_if(in_state(self.state.clone())),
+    );
+    app.add_systems(OnExit(self.state.clone()), save_name);
+  }
+}
+
+/// Text field to enter the name of a run that made it into the [`Leaderboard`].
+#[derive(Component)]
+struct NameInput {
+  position: usize,
+  focused: bool,
+}
+
+fn name_label(name: &str, focused: bool) -> String {
+  match focused {
+    true => format!("Name: {}_", name),
+    false => format!("Name: {}", name),
+  }
+}
+
+fn type_name(
+  mut keyboard_events: EventReader<KeyboardInput>,
+  mut query: Query<&mut NameInput>,
+  mut leaderboard: ResMut<Leaderboard>,
+) {
+  let Ok(mut input) = query.get_single_mut() else {
+    return;
+  };
+
+  for event in keyboard_events.read() {
+    if !input.focused || event.state != ButtonState::Pressed {
+      continue;
+    }
+
+    let Some(mut name) = leaderboard
+      .entries
+      .get(input.position)
+      .map(|entry| entry.name.clone())
+    else {
+      return;
+    };
+
+    match &event.logical_key {
+      Key::Enter => {
+        input.focused = false;
+        leaderboard.save();
+        continue;
+      }
+      Key::Backspace => {
+        name.pop();
+      }
+      // The space bar has its own key, it is not reported as a character
+      Key::Space => {
+        if name.chars().count() < NAME_LENGTH {
+          name.push(' ');
+        }
+      }
+      Key::Character(character) => {
+        let characters = character
+          .chars()
+          .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-' || *c == '_');
+        for character in characters {
+          if name.chars().count() < NAME_LENGTH {
+            name.push(character);
+          }
+        }
+      }
+      _ => continue,
+    }
+
+    leaderboard.rename(input.position, &name);
+  }
+}
+
+fn update_name_label(mut query: Query<(&NameInput, &mut Text)>, leaderboard: Res<Leaderboard>) {
+  for (input, mut text) in &mut query {
+    let Some(entry) = leaderboard.entries.get(input.position) else {
+      continue;
+    };
+
+    let label = name_label(&entry.name, input.focused);
+    if text.sections[0].value != label {
+      text.sections[0].value = label;
+    }
+  }
+}
+
+/// Save the name when leaving the screen, even if `Enter` was never pressed.
+fn save_name(query: Query<&NameInput>, leaderboard: Res<Leaderboard>) {
+  if query.iter().any(|input| input.focused) {
+    leaderboard.save();
+  }
+}
+
+/// Marker for the button that restarts the game with the same seed.
+#[derive(Component)]
+struct RetrySeedButton(u64);
+
+/// Marker for the button that plays the replay of the last run.
+#[derive(Component)]
+struct WatchReplayButton;
+
+fn watch_replay_on_press(
+  mut commands: Commands,
+  query: Query<&Interaction, (Changed<Interaction>, With<WatchReplayButton>)>,
+  last_replay: Option<Res<LastReplay>>,
+) {
+  let Some(last_replay) = last_replay else {
+    return;
+  };
+
+  for interaction in &query {
+    if interaction == &Interaction::Pressed {
+      start_replay(&mut commands, &last_replay.0);
+    }
   }
 }
 
-fn setup_game_over(mut commands: Commands, ui: Res<UiAssets>, score: Res<Score>) {
+fn retry_seed_on_press(
+  query: Query<(&Interaction, &RetrySeedButton), Changed<Interaction>>,
+  mut next_seed: ResMut<NextRunSeed>,
+) {
+  for (interaction, button) in &query {
+    if interaction == &Interaction::Pressed {
+      next_seed.0 = Some(button.0);
+    }
+  }
+}
+
+fn setup_game_over(
+  mut commands: Commands,
+  ui: Res<UiAssets>,
+  score: Res<Score>,
+  rng: Res<GameRng>,
+  last_replay: Option<Res<LastReplay>>,
+  mode: Res<GameMode>,
+  daily_best: Option<Res<DailyBest>>,
+  new_daily_best: Res<NewDailyBest>,
+  leaderboard: Res<Leaderboard>,
+  placement: Res<LeaderboardPlacement>,
+  stats: Res<RunStats>,
+  last_earnings: Res<LastEarnings>,
+  progression: Res<Progression>,
+) {
   commands.spawn((
     StateDespawnMarker,
     Camera2dBundle {
@@ -32,10 +203,10 @@ fn setup_game_over(mut commands: Commands, ui: Res<UiAssets>, score: Res<Score>)
           width: Val::Percent(100.),
           height: Val::Percent(100.),
           position_type: PositionType::Relative,
-          flex_direction: FlexDirection::Column,
+          flex_direction: FlexDirection::Row,
           justify_content: JustifyContent::Center,
           align_items: AlignItems::Center,
-          row_gap: Val::Px(32.),
+          column_gap: Val::Px(64.),
           ..Default::default()
         },
         ..Default::default()
@@ -43,6 +214,29 @@ fn setup_game_over(mut commands: Commands, ui: Res<UiAssets>, score: Res<Score>)
     ))
     .id();
 
+  let summary = commands
+    .spawn(NodeBundle {
+      style: Style {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(24.),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .id();
+
+  let records = commands
+    .spawn(NodeBundle {
+      style: Style {
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(16.),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .id();
+
   let play_again_button = commands
     .spawn((
       ButtonBundle::default(),
@@ -62,6 +256,48 @@ fn setup_game_over(mut commands: Commands, ui: Res<UiAssets>, score: Res<Score>)
     })
     .id();
 
+  let retry_seed_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      RetrySeedButton(rng.seed()),
+      StateOnPress {
+        action: AppState::InGame,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Retry Seed",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+    })
+    .id();
+
+  let watch_replay_button = last_replay.is_some().then(|| {
+    commands
+      .spawn((
+        ButtonBundle::default(),
+        WatchReplayButton,
+        StateOnPress {
+          action: AppState::InGame,
+        },
+      ))
+      .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+          "Watch Replay",
+          TextStyle {
+            font: ui.font_sans.clone(),
+            color: colors::PRIMARY_100,
+            font_size: 24.,
+          },
+        ));
+      })
+      .id()
+  });
+
   let game_over_text = commands
     .spawn(TextBundle::from_section(
       "Game Over",
@@ -84,7 +320,171 @@ fn setup_game_over(mut commands: Commands, ui: Res<UiAssets>, score: Res<Score>)
     ))
     .id();
 
+  let seed_text = commands
+    .spawn(TextBundle::from_section(
+      format!("Seed: {}", format_seed(rng.seed())),
+      TextStyle {
+        font: ui.font_mono.clone(),
+        color: colors::PRIMARY_300,
+        font_size: 20.,
+      },
+    ))
+    .id();
+
+  let mode_text = match *mode {
+    GameMode::Endless => "Endless".to_string(),
+    GameMode::Daily(date) if new_daily_best.0 => format!("Daily Challenge {} - New best!", date),
+    GameMode::Daily(date) => match daily_best.and_then(|best| best.of(date)) {
+      Some(best) => format!("Daily Challenge {} - Best: {:08}", date, best),
+      None => format!("Daily Challenge {}", date),
+    },
+  };
+
+  let mode_text = commands
+    .spawn(TextBundle::from_section(
+      mode_text,
+      TextStyle {
+        font: ui.font_sans.clone(),
+        color: colors::PRIMARY_300,
+        font_size: 24.,
+      },
+    ))
+    .id();
+
+  let stats_grid = spawn_stats(&mut commands, &ui, &stats);
+
+  let stardust_text = commands
+    .spawn(TextBundle::from_section(
+      format!(
+        "+{} Stardust ({} total)",
+        last_earnings.0, progression.stardust
+      ),
+      TextStyle {
+        font: ui.font_mono.clone(),
+        color: colors::RED_400,
+        font_size: 20.,
+      },
+    ))
+    .id();
+
+  if let Some(position) = placement.0 {
+    let name = &leaderboard.entries[position].name;
+    let name_input = commands
+      .spawn((
+        NameInput {
+          position,
+          focused: true,
+        },
+        TextBundle::from_section(
+          name_label(name, true),
+          TextStyle {
+            font: ui.font_mono.clone(),
+            color: colors::RED_400,
+            font_size: 20.,
+          },
+        ),
+      ))
+      .id();
+    commands.entity(records).push_children(&[name_input]);
+  }
+
+  let leaderboard = spawn_leaderboard(&mut commands, &ui, &leaderboard, placement.0);
+  commands.entity(records).push_children(&[leaderboard]);
+
   commands
     .entity(container)
-    .push_children(&[game_over_text, score_text, play_again_button]);
+    .push_children(&[summary, records]);
+
+  commands.entity(summary).push_children(&[
+    game_over_text,
+    mode_text,
+    score_text,
+    seed_text,
+    stats_grid,
+    stardust_text,
+    play_again_button,
+    retry_seed_button,
+  ]);
+
+  if let Some(watch_replay_button) = watch_replay_button {
+    commands
+      .entity(summary)
+      .push_children(&[watch_replay_button]);
+  }
+}
+
+/// Format a duration expressed in seconds as `mm:ss`.
+fn format_time(seconds: f32) -> String {
+  let seconds = seconds as u32;
+  format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Spawn the breakdown of the [`RunStats`], a grid of labels and values.
+fn spawn_stats(commands: &mut Commands, ui: &UiAssets, stats: &RunStats) -> Entity {
+  let cause_of_death = stats
+    .cause_of_death
+    .map(|cause| cause.to_string())
+    .unwrap_or_else(|| "-".to_string());
+
+  let rows = [
+    (
+      "Kills",
+      format!(
+        "{} (Aqua {} / Red {} / Green {})",
+        stats.kills(),
+        stats.aqua_kills,
+        stats.red_kills,
+        stats.green_kills
+      ),
+    ),
+    ("Largest multi-kill", stats.largest_multi_kill.to_string()),
+    (
+      "Shapes drawn",
+      format!(
+        "{} ({} valid / {} invalid)",
+        stats.shapes_drawn(),
+        stats.valid_attacks,
+        stats.invalid_attacks
+      ),
+    ),
+    ("Dodges", stats.dodges.to_string()),
+    ("Knocked into pits", stats.pit_kills.to_string()),
+    ("Meteors survived", stats.meteors_survived.to_string()),
+    ("Cycle reached", stats.cycle.to_string()),
+    ("Time alive", format_time(stats.time_alive)),
+    ("Cause of death", cause_of_death),
+  ];
+
+  commands
+    .spawn(NodeBundle {
+      style: Style {
+        display: Display::Grid,
+        column_gap: Val::Px(24.),
+        row_gap: Val::Px(4.),
+        grid_template_columns: vec![GridTrack::min_content(), GridTrack::flex(1.)],
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      for (label, value) in rows {
+        parent.spawn(TextBundle::from_section(
+          label,
+          TextStyle {
+            font: ui.font_sans.clone(),
+            color: colors::PRIMARY_300,
+            font_size: 18.,
+          },
+        ));
+        parent.spawn(TextBundle::from_section(
+          value,
+          TextStyle {
+            font: ui.font_mono.clone(),
+            color: colors::PRIMARY_100,
+            font_size: 18.,
+          },
+        ));
+      }
+    })
+    .id()
 }
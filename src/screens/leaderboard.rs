@@ -0,0 +1,101 @@
+use crate::{
+  assets::UiAssets,
+  game::{
+    leaderboard::{Leaderboard, LeaderboardEntry},
+    rng::format_seed,
+  },
+  prelude::*,
+};
+
+/// A row of the leaderboard table, holding the position of the run it shows.
+#[derive(Component)]
+pub struct LeaderboardRow(pub usize);
+
+/// Spawn the leaderboard table, highlighting the run at the given position.
+pub fn spawn_leaderboard(
+  commands: &mut Commands,
+  ui: &UiAssets,
+  leaderboard: &Leaderboard,
+  highlight: Option<usize>,
+) -> Entity {
+  commands
+    .spawn(NodeBundle {
+      style: Style {
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Start,
+        row_gap: Val::Px(4.),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "High Scores",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+
+      if leaderboard.entries.is_empty() {
+        parent.spawn(TextBundle::from_section(
+          "No runs yet",
+          TextStyle {
+            font: ui.font_mono.clone(),
+            color: colors::PRIMARY_300,
+            font_size: 14.,
+          },
+        ));
+      }
+
+      for (position, entry) in leaderboard.entries.iter().enumerate() {
+        let color = match highlight == Some(position) {
+          true => colors::RED_400,
+          false => colors::PRIMARY_300,
+        };
+
+        parent.spawn((
+          LeaderboardRow(position),
+          TextBundle::from_section(
+            row_text(position, entry),
+            TextStyle {
+              font: ui.font_mono.clone(),
+              color,
+              font_size: 14.,
+            },
+          ),
+        ));
+      }
+    })
+    .id()
+}
+
+pub fn row_text(position: usize, entry: &LeaderboardEntry) -> String {
+  format!(
+    "{:>2}. {:<12} {:08}  C{:02}  K{:03}  {}  {}",
+    position + 1,
+    entry.name,
+    entry.score,
+    entry.cycle,
+    entry.kills,
+    entry.date,
+    format_seed(entry.seed),
+  )
+}
+
+/// Keep the rows up to date, e.g. while the player types their name.
+pub fn update_leaderboard_rows(
+  leaderboard: Res<Leaderboard>,
+  mut query: Query<(&LeaderboardRow, &mut Text)>,
+) {
+  if !leaderboard.is_changed() {
+    return;
+  }
+
+  for (row, mut text) in &mut query {
+    if let Some(entry) = leaderboard.entries.get(row.0) {
+      text.sections[0].value = row_text(row.0, entry);
+    }
+  }
+}
@@ -0,0 +1,118 @@
+use crate::{
+  app_state::MainMenuSubState,
+  assets::UiAssets,
+  game::level::{Levels, SelectedLevel},
+  prelude::*,
+};
+
+use super::spawn_menu_overlay;
+
+/// Marker for the entities of the level select screen, drawn over the main menu.
+#[derive(Component)]
+struct LevelSelectScreen;
+
+/// Select the level of the next endless run when the button is pressed.
+#[derive(Component)]
+struct SelectLevelButton(usize);
+
+pub struct LevelSelectScreenPlugin;
+
+impl Plugin for LevelSelectScreenPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      OnEnter(MainMenuSubState::LevelSelect),
+      setup_level_select_screen,
+    );
+    app.add_systems(
+      Update,
+      (select_level_on_press, highlight_selected_level)
+        .chain()
+        .run_if(in_state(MainMenuSubState::LevelSelect)),
+    );
+    app.add_systems(
+      OnExit(MainMenuSubState::LevelSelect),
+      despawn_all_recursive::<LevelSelectScreen>,
+    );
+  }
+}
+
+fn setup_level_select_screen(mut commands: Commands, ui: Res<UiAssets>, levels: Res<Levels>) {
+  let list = commands
+    .spawn(NodeBundle {
+      style: Style {
+        column_gap: Val::Px(24.),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      for (index, level) in levels.0.iter().enumerate() {
+        parent
+          .spawn((
+            SelectLevelButton(index),
+            ButtonBundle {
+              style: Style {
+                padding: UiRect::all(Val::Px(16.)),
+                border: UiRect::all(Val::Px(2.)),
+                ..Default::default()
+              },
+              ..Default::default()
+            },
+          ))
+          .with_children(|parent| {
+            parent.spawn(
+              TextBundle::from_sections([
+                TextSection::new(
+                  format!("{}\n", level.name),
+                  TextStyle {
+                    font: ui.font_sans.clone(),
+                    color: colors::PRIMARY_300,
+                    font_size: 24.,
+                  },
+                ),
+                TextSection::new(
+                  format!("Difficulty x{:.2}", level.difficulty),
+                  TextStyle {
+                    font: ui.font_mono.clone(),
+                    color: colors::PRIMARY_300,
+                    font_size: 16.,
+                  },
+                ),
+              ])
+              .with_text_justify(JustifyText::Center),
+            );
+          });
+      }
+    })
+    .id();
+
+  spawn_menu_overlay(&mut commands, &ui, LevelSelectScreen, "Arenas", &[list]);
+}
+
+fn select_level_on_press(
+  query: Query<(&Interaction, &SelectLevelButton), Changed<Interaction>>,
+  mut selected_level: ResMut<SelectedLevel>,
+) {
+  for (interaction, button) in &query {
+    if interaction == &Interaction::Pressed && selected_level.0 != button.0 {
+      selected_level.0 = button.0;
+      selected_level.save();
+    }
+  }
+}
+
+/// Outline the selected level, including when the screen is opened.
+fn highlight_selected_level(
+  selected_level: Res<SelectedLevel>,
+  mut query: Query<(&SelectLevelButton, &mut BorderColor)>,
+) {
+  for (button, mut border_color) in &mut query {
+    let color = match button.0 == selected_level.0 {
+      true => colors::RED_400,
+      false => Color::NONE,
+    };
+    if border_color.0 != color {
+      border_color.0 = color;
+    }
+  }
+}
@@ -1,10 +1,26 @@
-use bevy::audio::{PlaybackMode, Volume};
+use bevy::{
+  audio::{PlaybackMode, Volume},
+  state::state::FreelyMutableState,
+};
+pub use seed::SeedInput;
+use seed::{focus_seed_input, spawn_seed_input, type_seed, update_seed_label};
 
 use crate::{
+  app_state::MainMenuSubState,
   assets::{MainMenuAssets, UiAssets},
+  game::{
+    daily::{self, DailyBest},
+    leaderboard::Leaderboard,
+    mode::GameMode,
+    rng::NextRunSeed,
+  },
   prelude::*,
 };
 
+use super::leaderboard::spawn_leaderboard;
+
+mod seed;
+
 #[allow(dead_code)]
 #[derive(Default, Component)]
 enum AudioStatus {
@@ -22,6 +38,10 @@ pub struct StateOnPress<S: States> {
   pub action: S,
 }
 
+/// Select the [`GameMode`] of the next run when the button is pressed.
+#[derive(Component)]
+pub struct GameModeOnPress(pub GameMode);
+
 pub struct MainMenuPlugin<S: States> {
   pub state: S,
 }
@@ -33,8 +53,25 @@ impl<S: States> Plugin for MainMenuPlugin<S> {
       (setup_main_menu, play_main_menu_audio),
     );
     app.add_systems(Update, style_interaction);
-    app.add_systems(Update, action_on_press.before(style_interaction));
+    app.add_systems(
+      Update,
+      (
+        action_on_press::<AppState>,
+        action_on_press::<MainMenuSubState>,
+      )
+        .before(style_interaction),
+    );
+    app.add_systems(
+      Update,
+      game_mode_on_press.before(action_on_press::<AppState>),
+    );
     app.add_systems(Update, check_audio_playback);
+    app.add_systems(
+      Update,
+      (focus_seed_input, type_seed, update_seed_label)
+        .chain()
+        .run_if(in_state(self.state.clone())),
+    );
     app.add_systems(OnExit(self.state.clone()), reset_mouse_icon);
   }
 }
@@ -62,7 +99,14 @@ fn check_audio_playback(
   };
 }
 
-fn setup_main_menu(mut commands: Commands, ui: Res<UiAssets>, main_menu_ui: Res<MainMenuAssets>) {
+fn setup_main_menu(
+  mut commands: Commands,
+  ui: Res<UiAssets>,
+  main_menu_ui: Res<MainMenuAssets>,
+  next_seed: Res<NextRunSeed>,
+  daily_best: Option<Res<DailyBest>>,
+  leaderboard: Res<Leaderboard>,
+) {
   // spawn a camera that despawn when `MainMenuState` is left
   commands.spawn((
     StateDespawnMarker,
@@ -100,6 +144,7 @@ fn setup_main_menu(mut commands: Commands, ui: Res<UiAssets>, main_menu_ui: Res<
       StateOnPress {
         action: AppState::InGame,
       },
+      GameModeOnPress(GameMode::Endless),
     ))
     .with_children(|parent| {
       parent.spawn(TextBundle::from_section(
@@ -113,6 +158,147 @@ fn setup_main_menu(mut commands: Commands, ui: Res<UiAssets>, main_menu_ui: Res<
     })
     .id();
 
+  let today = Date::today();
+  let daily_details = match daily_best.and_then(|best| best.of(today)) {
+    Some(best) => format!(
+      "{}\nBest: {:08}",
+      daily::describe(&daily::modifiers(today)),
+      best
+    ),
+    None => daily::describe(&daily::modifiers(today)),
+  };
+
+  let daily_button = commands
+    .spawn((
+      ButtonBundle {
+        style: Style {
+          flex_direction: FlexDirection::Column,
+          align_items: AlignItems::Center,
+          row_gap: Val::Px(4.),
+          ..Default::default()
+        },
+        ..Default::default()
+      },
+      StateOnPress {
+        action: AppState::InGame,
+      },
+      GameModeOnPress(GameMode::Daily(today)),
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Daily Challenge",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 32.,
+        },
+      ));
+      parent.spawn(
+        TextBundle::from_section(
+          daily_details,
+          TextStyle {
+            font: ui.font_mono.clone(),
+            color: colors::PRIMARY_300,
+            font_size: 16.,
+          },
+        )
+        .with_text_justify(JustifyText::Center),
+      );
+    })
+    .id();
+
+  let achievements_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      StateOnPress {
+        action: MainMenuSubState::Achievements,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Achievements",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+    })
+    .id();
+
+  let upgrades_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      StateOnPress {
+        action: MainMenuSubState::Upgrades,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Upgrades",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+    })
+    .id();
+
+  let settings_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      StateOnPress {
+        action: MainMenuSubState::Settings,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Settings",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+    })
+    .id();
+
+  let level_select_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      StateOnPress {
+        action: MainMenuSubState::LevelSelect,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Arenas",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 24.,
+        },
+      ));
+    })
+    .id();
+
+  let leaderboard = spawn_leaderboard(&mut commands, &ui, &leaderboard, None);
+  let leaderboard_panel = commands
+    .spawn(NodeBundle {
+      style: Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(64.),
+        right: Val::Px(16.),
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .push_children(&[leaderboard])
+    .id();
+
+  let seed_input = spawn_seed_input(&mut commands, &ui, &next_seed);
+
   let volume_icon = commands
     .spawn((
       ButtonBundle {
@@ -214,9 +400,18 @@ fn setup_main_menu(mut commands: Commands, ui: Res<UiAssets>, main_menu_ui: Res<
     })
     .id();
 
-  commands
-    .entity(container)
-    .push_children(&[play_button, volume_icon, legend_grid]);
+  commands.entity(container).push_children(&[
+    play_button,
+    daily_button,
+    level_select_button,
+    upgrades_button,
+    achievements_button,
+    settings_button,
+    seed_input,
+    volume_icon,
+    legend_grid,
+    leaderboard_panel,
+  ]);
 }
 
 fn play_main_menu_audio(mut commands: Commands, audio: Res<MainMenuAssets>) {
@@ -286,16 +481,27 @@ fn style_interaction(
   }
 }
 
-fn action_on_press(
+pub fn action_on_press<S: FreelyMutableState>(
   mut interaction_query: Query<
-    (&Interaction, &StateOnPress<AppState>),
+    (&Interaction, &StateOnPress<S>),
     (Changed<Interaction>, With<Button>),
   >,
-  mut next_state: ResMut<NextState<AppState>>,
+  mut next_state: ResMut<NextState<S>>,
 ) {
   for (interaction, state) in &mut interaction_query {
     if interaction == &Interaction::Pressed {
-      next_state.set(state.action);
+      next_state.set(state.action.clone());
+    }
+  }
+}
+
+fn game_mode_on_press(
+  interaction_query: Query<(&Interaction, &GameModeOnPress), Changed<Interaction>>,
+  mut game_mode: ResMut<GameMode>,
+) {
+  for (interaction, mode) in &interaction_query {
+    if interaction == &Interaction::Pressed {
+      *game_mode = mode.0;
     }
   }
 }
@@ -0,0 +1,112 @@
+use bevy::input::{
+  keyboard::{Key, KeyboardInput},
+  ButtonState,
+};
+
+use crate::{
+  assets::UiAssets,
+  game::rng::{format_seed, parse_seed, NextRunSeed},
+  prelude::*,
+};
+
+/// Max length of a seed, see [`format_seed`].
+const SEED_LENGTH: usize = 16;
+
+/// Text field used to start a run from a given seed.
+/// Click it to start typing, press `Enter` to confirm.
+#[derive(Component, Default)]
+pub struct SeedInput {
+  value: String,
+  focused: bool,
+}
+
+pub fn spawn_seed_input(commands: &mut Commands, ui: &UiAssets, next_seed: &NextRunSeed) -> Entity {
+  let input = SeedInput {
+    value: next_seed.0.map(format_seed).unwrap_or_default(),
+    focused: false,
+  };
+  let label = seed_label(&input);
+
+  commands
+    .spawn((ButtonBundle::default(), input))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        label,
+        TextStyle {
+          font: ui.font_mono.clone(),
+          color: colors::PRIMARY_300,
+          font_size: 20.,
+        },
+      ));
+    })
+    .id()
+}
+
+impl SeedInput {
+  pub fn is_focused(&self) -> bool {
+    self.focused
+  }
+}
+
+fn seed_label(input: &SeedInput) -> String {
+  let value = match (input.value.is_empty(), input.focused) {
+    (true, false) => "random".to_string(),
+    (_, true) => format!("{}_", input.value),
+    (false, false) => input.value.clone(),
+  };
+
+  format!("Seed: {}", value)
+}
+
+pub fn focus_seed_input(mut query: Query<(&Interaction, &mut SeedInput), Changed<Interaction>>) {
+  for (interaction, mut input) in &mut query {
+    if interaction == &Interaction::Pressed {
+      input.focused = !input.focused;
+    }
+  }
+}
+
+pub fn type_seed(
+  mut keyboard_events: EventReader<KeyboardInput>,
+  mut query: Query<&mut SeedInput>,
+  mut next_seed: ResMut<NextRunSeed>,
+) {
+  let Ok(mut input) = query.get_single_mut() else {
+    return;
+  };
+
+  for event in keyboard_events.read() {
+    if !input.focused || event.state != ButtonState::Pressed {
+      continue;
+    }
+
+    match &event.logical_key {
+      Key::Enter | Key::Escape => input.focused = false,
+      Key::Backspace => {
+        input.value.pop();
+      }
+      Key::Character(character) => {
+        let digits = character.chars().filter(char::is_ascii_hexdigit);
+        for digit in digits {
+          if input.value.len() < SEED_LENGTH {
+            input.value.push(digit.to_ascii_uppercase());
+          }
+        }
+      }
+      _ => {}
+    }
+
+    next_seed.0 = parse_seed(&input.value);
+  }
+}
+
+pub fn update_seed_label(
+  query: Query<(&SeedInput, &Children), Changed<SeedInput>>,
+  mut text_query: Query<&mut Text>,
+) {
+  for (input, children) in &query {
+    if let Ok(mut text) = text_query.get_mut(children[0]) {
+      text.sections[0].value = seed_label(input);
+    }
+  }
+}
@@ -1,3 +1,84 @@
+pub mod achievements;
 pub mod game_over;
+pub mod leaderboard;
+pub mod level_select;
 pub mod loading;
 pub mod main_menu;
+pub mod settings;
+pub mod upgrades;
+
+use bevy::ui::FocusPolicy;
+
+use crate::{app_state::MainMenuSubState, assets::UiAssets, prelude::*};
+
+use self::main_menu::StateOnPress;
+
+/// Spawn a full screen overlay drawn over the main menu, with a title,
+/// the given `content` and a button back to the main menu.
+pub fn spawn_menu_overlay(
+  commands: &mut Commands,
+  ui: &UiAssets,
+  marker: impl Component,
+  title: impl Into<String>,
+  content: &[Entity],
+) {
+  let container = commands
+    .spawn((
+      StateDespawnMarker,
+      marker,
+      NodeBundle {
+        style: Style {
+          width: Val::Percent(100.),
+          height: Val::Percent(100.),
+          position_type: PositionType::Absolute,
+          flex_direction: FlexDirection::Column,
+          justify_content: JustifyContent::Center,
+          align_items: AlignItems::Center,
+          row_gap: Val::Px(24.),
+          ..Default::default()
+        },
+        background_color: colors::PRIMARY_800.into(),
+        // Keep the main menu buttons below from being pressed
+        focus_policy: FocusPolicy::Block,
+        z_index: ZIndex::Global(10),
+        ..Default::default()
+      },
+    ))
+    .id();
+
+  let title = commands
+    .spawn(TextBundle::from_section(
+      title,
+      TextStyle {
+        font: ui.font_sans.clone(),
+        color: colors::PRIMARY_100,
+        font_size: 48.,
+      },
+    ))
+    .id();
+
+  let back_button = commands
+    .spawn((
+      ButtonBundle::default(),
+      StateOnPress {
+        action: MainMenuSubState::None,
+      },
+    ))
+    .with_children(|parent| {
+      parent.spawn(TextBundle::from_section(
+        "Back",
+        TextStyle {
+          font: ui.font_sans.clone(),
+          color: colors::PRIMARY_100,
+          font_size: 32.,
+        },
+      ));
+    })
+    .id();
+
+  commands
+    .entity(container)
+    .add_child(title)
+    .push_children(content)
+    .add_child(back_button);
+}
@@ -0,0 +1,126 @@
+use crate::{
+  app_state::MainMenuSubState,
+  assets::UiAssets,
+  game::post_process::{GraphicsSettings, ScreenEffect},
+  prelude::*,
+};
+
+use super::spawn_menu_overlay;
+
+/// Marker for the entities of the settings screen, drawn over the main menu.
+#[derive(Component)]
+struct SettingsScreen;
+
+/// Turn the effect on or off when the button is pressed.
+#[derive(Component)]
+struct ToggleEffectButton(ScreenEffect);
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(MainMenuSubState::Settings), setup_settings_screen);
+    app.add_systems(
+      Update,
+      (toggle_effect_on_press, update_settings_screen)
+        .chain()
+        .run_if(in_state(MainMenuSubState::Settings)),
+    );
+    app.add_systems(
+      OnExit(MainMenuSubState::Settings),
+      despawn_all_recursive::<SettingsScreen>,
+    );
+  }
+}
+
+fn toggle_label(settings: &GraphicsSettings, effect: ScreenEffect) -> &'static str {
+  match settings.is_enabled(effect) {
+    true => "On",
+    false => "Off",
+  }
+}
+
+fn setup_settings_screen(
+  mut commands: Commands,
+  ui: Res<UiAssets>,
+  settings: Res<GraphicsSettings>,
+) {
+  let list = commands
+    .spawn(NodeBundle {
+      style: Style {
+        display: Display::Grid,
+        column_gap: Val::Px(32.),
+        row_gap: Val::Px(12.),
+        align_items: AlignItems::Center,
+        grid_template_columns: vec![GridTrack::min_content(), GridTrack::min_content()],
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      for effect in ScreenEffect::ALL {
+        parent.spawn(TextBundle::from_sections([
+          TextSection::new(
+            format!("{}\n", effect.title()),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color: colors::PRIMARY_100,
+              font_size: 24.,
+            },
+          ),
+          TextSection::new(
+            effect.description(),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color: colors::PRIMARY_300,
+              font_size: 16.,
+            },
+          ),
+        ]));
+
+        parent
+          .spawn((ButtonBundle::default(), ToggleEffectButton(effect)))
+          .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+              toggle_label(&settings, effect),
+              TextStyle {
+                font: ui.font_mono.clone(),
+                color: colors::PRIMARY_300,
+                font_size: 20.,
+              },
+            ));
+          });
+      }
+    })
+    .id();
+
+  spawn_menu_overlay(&mut commands, &ui, SettingsScreen, "Settings", &[list]);
+}
+
+fn toggle_effect_on_press(
+  query: Query<(&Interaction, &ToggleEffectButton), Changed<Interaction>>,
+  mut settings: ResMut<GraphicsSettings>,
+) {
+  for (interaction, button) in &query {
+    if interaction == &Interaction::Pressed {
+      settings.toggle(button.0);
+      settings.save();
+    }
+  }
+}
+
+fn update_settings_screen(
+  settings: Res<GraphicsSettings>,
+  button_query: Query<(&ToggleEffectButton, &Children)>,
+  mut text_query: Query<&mut Text>,
+) {
+  if !settings.is_changed() {
+    return;
+  }
+
+  for (button, children) in &button_query {
+    if let Ok(mut text) = text_query.get_mut(children[0]) {
+      text.sections[0].value = toggle_label(&settings, button.0).to_string();
+    }
+  }
+}
@@ -0,0 +1,186 @@
+use crate::{
+  app_state::MainMenuSubState,
+  assets::UiAssets,
+  game::progression::{Progression, Upgrade},
+  prelude::*,
+};
+
+use super::spawn_menu_overlay;
+
+/// Marker for the entities of the upgrades screen, drawn over the main menu.
+#[derive(Component)]
+struct UpgradesScreen;
+
+#[derive(Component)]
+struct StardustText;
+
+/// Shows the level of an upgrade.
+#[derive(Component)]
+struct UpgradeLevelText(Upgrade);
+
+/// Buy the next level of the upgrade when the button is pressed.
+#[derive(Component)]
+struct BuyUpgradeButton(Upgrade);
+
+pub struct UpgradesScreenPlugin;
+
+impl Plugin for UpgradesScreenPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(OnEnter(MainMenuSubState::Upgrades), setup_upgrades_screen);
+    app.add_systems(
+      Update,
+      (buy_upgrade_on_press, update_upgrades_screen)
+        .chain()
+        .run_if(in_state(MainMenuSubState::Upgrades)),
+    );
+    app.add_systems(
+      OnExit(MainMenuSubState::Upgrades),
+      despawn_all_recursive::<UpgradesScreen>,
+    );
+  }
+}
+
+fn stardust_label(progression: &Progression) -> String {
+  format!("Stardust: {}", progression.stardust)
+}
+
+fn level_label(progression: &Progression, upgrade: Upgrade) -> String {
+  format!(
+    "Lv {}/{}",
+    progression.levels.level(upgrade),
+    upgrade.max_level()
+  )
+}
+
+fn buy_label(progression: &Progression, upgrade: Upgrade) -> String {
+  match upgrade.cost(progression.levels.level(upgrade)) {
+    Some(cost) => format!("Buy {}", cost),
+    None => "Maxed".to_string(),
+  }
+}
+
+fn setup_upgrades_screen(mut commands: Commands, ui: Res<UiAssets>, progression: Res<Progression>) {
+  let stardust_text = commands
+    .spawn((
+      StardustText,
+      TextBundle::from_section(
+        stardust_label(&progression),
+        TextStyle {
+          font: ui.font_mono.clone(),
+          color: colors::RED_400,
+          font_size: 24.,
+        },
+      ),
+    ))
+    .id();
+
+  let list = commands
+    .spawn(NodeBundle {
+      style: Style {
+        display: Display::Grid,
+        column_gap: Val::Px(32.),
+        row_gap: Val::Px(12.),
+        align_items: AlignItems::Center,
+        grid_template_columns: vec![
+          GridTrack::min_content(),
+          GridTrack::min_content(),
+          GridTrack::min_content(),
+        ],
+        ..Default::default()
+      },
+      ..Default::default()
+    })
+    .with_children(|parent| {
+      for upgrade in Upgrade::ALL {
+        parent.spawn(TextBundle::from_sections([
+          TextSection::new(
+            format!("{}\n", upgrade.title()),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color: colors::PRIMARY_100,
+              font_size: 24.,
+            },
+          ),
+          TextSection::new(
+            upgrade.description(),
+            TextStyle {
+              font: ui.font_sans.clone(),
+              color: colors::PRIMARY_300,
+              font_size: 16.,
+            },
+          ),
+        ]));
+
+        parent.spawn((
+          UpgradeLevelText(upgrade),
+          TextBundle::from_section(
+            level_label(&progression, upgrade),
+            TextStyle {
+              font: ui.font_mono.clone(),
+              color: colors::PRIMARY_300,
+              font_size: 20.,
+            },
+          ),
+        ));
+
+        parent
+          .spawn((ButtonBundle::default(), BuyUpgradeButton(upgrade)))
+          .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+              buy_label(&progression, upgrade),
+              TextStyle {
+                font: ui.font_mono.clone(),
+                color: colors::PRIMARY_300,
+                font_size: 20.,
+              },
+            ));
+          });
+      }
+    })
+    .id();
+
+  spawn_menu_overlay(
+    &mut commands,
+    &ui,
+    UpgradesScreen,
+    "Upgrades",
+    &[stardust_text, list],
+  );
+}
+
+fn buy_upgrade_on_press(
+  query: Query<(&Interaction, &BuyUpgradeButton), Changed<Interaction>>,
+  mut progression: ResMut<Progression>,
+) {
+  for (interaction, button) in &query {
+    if interaction == &Interaction::Pressed && progression.buy(button.0) {
+      progression.save();
+    }
+  }
+}
+
+fn update_upgrades_screen(
+  progression: Res<Progression>,
+  mut stardust_query: Query<&mut Text, With<StardustText>>,
+  mut level_query: Query<(&UpgradeLevelText, &mut Text), Without<StardustText>>,
+  button_query: Query<(&BuyUpgradeButton, &Children)>,
+  mut text_query: Query<&mut Text, (Without<StardustText>, Without<UpgradeLevelText>)>,
+) {
+  if !progression.is_changed() {
+    return;
+  }
+
+  for mut text in &mut stardust_query {
+    text.sections[0].value = stardust_label(&progression);
+  }
+
+  for (level, mut text) in &mut level_query {
+    text.sections[0].value = level_label(&progression, level.0);
+  }
+
+  for (button, children) in &button_query {
+    if let Ok(mut text) = text_query.get_mut(children[0]) {
+      text.sections[0].value = buy_label(&progression, button.0);
+    }
+  }
+}
@@ -0,0 +1,69 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::prelude::*;
+
+/// Load a value saved with [`save`].
+///
+/// Values are stored as RON files in the `saves` directory on native,
+/// and in the `localStorage` of the browser on wasm.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+  let content = read(key)?;
+
+  match ron::from_str(&content) {
+    Ok(value) => Some(value),
+    Err(err) => {
+      warn!("Could not parse saved {}: {}", key, err);
+      None
+    }
+  }
+}
+
+/// Save a value, see [`load`].
+pub fn save<T: Serialize>(key: &str, value: &T) {
+  let result = ron::to_string(value)
+    .map_err(|err| err.to_string())
+    .and_then(|content| write(key, &content));
+
+  if let Err(err) = result {
+    warn!("Could not save {}: {}", key, err);
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const SAVES_DIR: &str = "saves";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+  let path = std::path::Path::new(SAVES_DIR).join(format!("{}.ron", key));
+  std::fs::read_to_string(path).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, content: &str) -> Result<(), String> {
+  let path = std::path::Path::new(SAVES_DIR).join(format!("{}.ron", key));
+
+  std::fs::create_dir_all(SAVES_DIR)
+    .and_then(|_| std::fs::write(path, content))
+    .map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+  web_sys::window()?.local_storage().ok().flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+  local_storage()?
+    .get_item(&format!("take-cover:{}", key))
+    .ok()
+    .flatten()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, content: &str) -> Result<(), String> {
+  local_storage()
+    .ok_or_else(|| "localStorage is not available".to_string())?
+    .set_item(&format!("take-cover:{}", key), content)
+    .map_err(|err| format!("{:?}", err))
+}
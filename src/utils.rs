@@ -16,3 +16,55 @@ pub fn despawn_all_recursive<C: Component>(mut commands: Commands, query: Query<
     commands.entity(entity).despawn_recursive();
   }
 }
+
+/// A calendar date, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Date {
+  pub year: i32,
+  pub month: u32,
+  pub day: u32,
+}
+
+impl Date {
+  /// Today's date, in UTC.
+  pub fn today() -> Self {
+    Self::from_unix_days(unix_days())
+  }
+
+  /// Convert the number of days since the unix epoch to a date,
+  /// see <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+  pub fn from_unix_days(days: i64) -> Self {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+
+    Self { year, month, day }
+  }
+}
+
+impl std::fmt::Display for Date {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn unix_days() -> i64 {
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .unwrap_or_default();
+
+  (now.as_secs() / 86_400) as i64
+}
+
+#[cfg(target_arch = "wasm32")]
+fn unix_days() -> i64 {
+  // `SystemTime` is not available in the browser
+  (js_sys::Date::now() / 86_400_000.) as i64
+}